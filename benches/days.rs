@@ -0,0 +1,22 @@
+use advent2018::input::load_day;
+use advent2018::solution::DAYS;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// Parsing and each part of every day, as separate benchmarks
+fn days(c: &mut Criterion) {
+    for solver in DAYS.iter() {
+        let day = solver.day();
+        let input = load_day(day).unwrap();
+        let parsed = solver.parse(&input).unwrap();
+
+        let mut group = c.benchmark_group(format!("day{}", day));
+        group.sample_size(10);
+        group.bench_function("parse", |b| b.iter(|| solver.parse(&input).unwrap()));
+        group.bench_function("part1", |b| b.iter(|| parsed.part1()));
+        group.bench_function("part2", |b| b.iter(|| parsed.part2()));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, days);
+criterion_main!(benches);
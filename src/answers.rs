@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use regex::Regex;
+
+use crate::error::ParseError;
+use crate::input::inputs_dir;
+
+/// Expected answers keyed by day and part, read from a small subset of TOML:
+///
+/// ```toml
+/// [day1]
+/// part1 = 508
+/// part2 = "549"
+///
+/// [day10]
+/// part1 = """
+/// #...#
+/// """
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct Answers(BTreeMap<(u8, u8), String>);
+
+impl Answers {
+    pub fn get(&self, day: u8, part: u8) -> Option<&str> {
+        self.0.get(&(day, part)).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, day: u8, part: u8, answer: impl Into<String>) {
+        self.0.insert((day, part), answer.into());
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn parse(input: &str) -> Result<Answers, ParseError> {
+        lazy_static! {
+            static ref TABLE: Regex = Regex::new(r"^\[day(?P<day>\d+)\]$").unwrap();
+            static ref KEY: Regex = Regex::new(r"^part(?P<part>[12]) *= *(?P<value>.*)$").unwrap();
+            static ref VALUE: Regex =
+                Regex::new(r#"^(?:"(?P<string>[^"\\]*)"|(?P<integer>-?\d+))$"#).unwrap();
+        }
+
+        let mut answers = Answers::default();
+        let mut day = None;
+        let mut lines = input.lines().enumerate();
+
+        while let Some((index, raw_line)) = lines.next() {
+            let error =
+                |offset, expected| ParseError::new(raw_line, offset, expected).at_line(index + 1);
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(captures) = TABLE.captures(line) {
+                day = Some(
+                    captures["day"]
+                        .parse()
+                        .map_err(|_| error(0, "a day number that fits in u8"))?,
+                );
+                continue;
+            }
+
+            let captures = KEY
+                .captures(line)
+                .ok_or_else(|| error(0, "'[dayN]' or 'partN = <answer>'"))?;
+            let day = day.ok_or_else(|| error(0, "a '[dayN]' table before any answers"))?;
+            let part = captures["part"].parse().unwrap();
+            let value_start = raw_line.len() - raw_line.trim_start().len()
+                + captures.name("value").unwrap().start();
+            let value = &captures["value"];
+
+            let answer = if value == r#"""""# {
+                // multi-line string, which runs until the closing quotes on a line of their own
+                let mut text = Vec::new();
+                loop {
+                    match lines.next() {
+                        Some((_, r#"""""#)) => break,
+                        Some((_, text_line)) => text.push(text_line),
+                        None => return Err(error(value_start, "a closing '\"\"\"'")),
+                    }
+                }
+                text.join("\n")
+            } else {
+                let captures = VALUE
+                    .captures(value)
+                    .ok_or_else(|| error(value_start, "a quoted string, an integer or '\"\"\"'"))?;
+                captures
+                    .name("string")
+                    .or_else(|| captures.name("integer"))
+                    .unwrap()
+                    .as_str()
+                    .to_string()
+            };
+            answers.insert(day, part, answer);
+        }
+
+        Ok(answers)
+    }
+}
+
+/// `answers.toml`, alongside the inputs
+pub fn default_path() -> PathBuf {
+    inputs_dir().join("answers.toml")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_strings_integers_and_multiline_strings() {
+        let answers = Answers::parse(
+            r#"
+# my answers
+[day1]
+part1 = 508
+part2 = "549"
+
+[day10]
+part1 = """
+#..#
+####
+"""
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(answers.len(), 3);
+        assert_eq!(answers.get(1, 1), Some("508"));
+        assert_eq!(answers.get(1, 2), Some("549"));
+        assert_eq!(answers.get(10, 1), Some("#..#\n####"));
+        assert_eq!(answers.get(10, 2), None);
+    }
+
+    #[test]
+    fn reports_malformed_lines() {
+        let error = Answers::parse("part1 = 5").unwrap_err();
+        assert_eq!(error.line, 1);
+
+        let error = Answers::parse("[day1]\npart1 = five").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 9));
+
+        let error = Answers::parse("[day1]\npart3 = 5").unwrap_err();
+        assert_eq!(error.line, 2);
+
+        let error = Answers::parse("[day1]\npart1 = \"\"\"\nfoo").unwrap_err();
+        assert_eq!(error.expected, "a closing '\"\"\"'");
+    }
+}
@@ -0,0 +1,124 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+use crate::error::ParseError;
+use crate::solution::Solver;
+
+/// Runs `f` once, returning its result and how long it took
+pub fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+/// Summary of repeated runs of one stage
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub runs: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[Duration]) -> Stats {
+        assert!(!samples.is_empty(), "need at least one sample");
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        Stats {
+            runs: sorted.len(),
+            min: sorted[0],
+            median: sorted[sorted.len() / 2],
+            max: sorted[sorted.len() - 1],
+        }
+    }
+
+    fn measure(runs: usize, mut f: impl FnMut()) -> Stats {
+        let samples: Vec<Duration> = (0..runs).map(|_| time(&mut f).1).collect();
+        Stats::from_samples(&samples)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DayTimings {
+    pub day: u8,
+    pub parse: Stats,
+    pub part1: Stats,
+    pub part2: Stats,
+}
+
+/// Times parsing and each part separately, `runs` times each
+pub fn bench(solver: &dyn Solver, input: &str, runs: usize) -> Result<DayTimings, ParseError> {
+    let parsed = solver.parse(input)?;
+
+    Ok(DayTimings {
+        day: solver.day(),
+        parse: Stats::measure(runs, || {
+            solver.parse(input).unwrap();
+        }),
+        part1: Stats::measure(runs, || {
+            parsed.part1();
+        }),
+        part2: Stats::measure(runs, || {
+            parsed.part2();
+        }),
+    })
+}
+
+impl Display for DayTimings {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let stages = [
+            ("parse", self.parse),
+            ("part 1", self.part1),
+            ("part 2", self.part2),
+        ];
+        for (stage, stats) in stages.iter() {
+            writeln!(
+                f,
+                "day {:>2}  {:<6}  {:>12?}  {:>12?}  {:>12?}",
+                self.day, stage, stats.min, stats.median, stats.max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Column headings matching `DayTimings`' `Display`
+pub const HEADER: &str = "day     stage            min        median           max";
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solution::find;
+
+    #[test]
+    fn stats_summarise_samples() {
+        let millis = |ms| Duration::from_millis(ms);
+        let stats = Stats::from_samples(&[millis(5), millis(1), millis(3), millis(9), millis(4)]);
+        assert_eq!(
+            stats,
+            Stats {
+                runs: 5,
+                min: millis(1),
+                median: millis(4),
+                max: millis(9),
+            }
+        );
+    }
+
+    #[test]
+    fn times_every_stage() {
+        let timings = bench(find(8).unwrap(), "2 3 0 3 10 11 12 1 1 0 1 99 2 1 1 2", 3).unwrap();
+        assert_eq!(timings.day, 8);
+        assert_eq!(timings.parse.runs, 3);
+        assert_eq!(timings.part1.runs, 3);
+        assert_eq!(timings.part2.runs, 3);
+        assert!(timings.part2.min <= timings.part2.max);
+    }
+
+    #[test]
+    fn parse_errors_are_reported() {
+        assert!(bench(find(8).unwrap(), "2 3 0", 3).is_err());
+    }
+}
@@ -0,0 +1,317 @@
+//! A similarity index over day 2 box IDs, for finding the known IDs closest to a scanned one.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::iter::FromIterator;
+
+use crate::day2::{diff_strings, levenshtein};
+use crate::error::ParseError;
+
+/// A BK-tree, answering queries in `diff_strings` distance.
+///
+/// The tree itself is organised by `levenshtein` distance, because `diff_strings` mixes Hamming
+/// and Levenshtein distances and so breaks the triangle inequality the pruning relies on. Edit
+/// distance never exceeds `diff_strings` distance, so it is still a safe lower bound.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BkTree {
+    /// the root, if any, is `nodes[0]`
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Node {
+    id: String,
+    /// child index by its edit distance from this node
+    children: BTreeMap<usize, usize>,
+}
+
+impl BkTree {
+    pub fn new() -> BkTree {
+        BkTree::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Adds `id` unless it's already present, returning whether it was added
+    pub fn insert(&mut self, id: &str) -> bool {
+        let new_index = self.nodes.len();
+        let mut index = 0;
+        if !self.nodes.is_empty() {
+            loop {
+                let distance = levenshtein(id, &self.nodes[index].id);
+                if distance == 0 {
+                    return false;
+                }
+                match self.nodes[index].children.get(&distance) {
+                    Some(&child) => index = child,
+                    None => {
+                        self.nodes[index].children.insert(distance, new_index);
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.nodes.push(Node {
+            id: id.to_string(),
+            children: BTreeMap::new(),
+        });
+        true
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        !self.within(id, 0).is_empty()
+    }
+
+    /// Every ID within distance `k` of `id`, closest first
+    pub fn within(&self, id: &str, k: usize) -> Vec<(usize, &str)> {
+        let mut found = Vec::new();
+        let mut stack: Vec<usize> = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let edit_distance = levenshtein(id, &node.id);
+            if edit_distance <= k {
+                let distance = diff_strings(id, &node.id).0;
+                if distance <= k {
+                    found.push((distance, node.id.as_str()));
+                }
+            }
+
+            let nearest_child = edit_distance.saturating_sub(k);
+            let furthest_child = edit_distance.saturating_add(k);
+            stack.extend(
+                node.children
+                    .range(nearest_child..=furthest_child)
+                    .map(|(_, &c)| c),
+            );
+        }
+
+        found.sort();
+        found
+    }
+
+    /// The `n` closest IDs to `id`, closest first, breaking ties alphabetically
+    pub fn nearest(&self, id: &str, n: usize) -> Vec<(usize, &str)> {
+        // the worst of the best so far is on top
+        let mut best: BinaryHeap<(usize, &str)> = BinaryHeap::new();
+        // nodes still to visit, with a lower bound on the distance of anything under them
+        let mut stack: Vec<(usize, usize)> = if self.nodes.is_empty() || n == 0 {
+            vec![]
+        } else {
+            vec![(0, 0)]
+        };
+        let bound = |best: &BinaryHeap<(usize, &str)>| match best.peek() {
+            Some(&(worst, _)) if best.len() == n => worst,
+            _ => usize::MAX,
+        };
+
+        while let Some((index, lower_bound)) = stack.pop() {
+            if lower_bound > bound(&best) {
+                continue;
+            }
+
+            let node = &self.nodes[index];
+            let edit_distance = levenshtein(id, &node.id);
+            let distance = diff_strings(id, &node.id).0;
+            best.push((distance, node.id.as_str()));
+            if best.len() > n {
+                best.pop();
+            }
+
+            // visit the most promising children first, so the bound tightens quickly
+            let mut children: Vec<(usize, usize)> = node
+                .children
+                .iter()
+                .map(|(&child_distance, &child)| (child, edit_distance.abs_diff(child_distance)))
+                .filter(|&(_, lower_bound)| lower_bound <= bound(&best))
+                .collect();
+            children.sort_by_key(|&(_, lower_bound)| Reverse(lower_bound));
+            stack.extend(children);
+        }
+
+        best.into_sorted_vec()
+    }
+
+    /// One line per node, in insertion order: the parent's line number (`-` for the root), the
+    /// edit distance from it, then the ID
+    pub fn serialize(&self) -> String {
+        let mut parents = vec![None; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            for (&distance, &child) in &node.children {
+                parents[child] = Some((index, distance));
+            }
+        }
+
+        self.nodes
+            .iter()
+            .zip(parents)
+            .map(|(node, parent)| match parent {
+                Some((parent, distance)) => format!("{} {} {}\n", parent + 1, distance, node.id),
+                None => format!("- 0 {}\n", node.id),
+            })
+            .collect()
+    }
+
+    /// Reads the output of `serialize`, without recomputing any distances
+    pub fn parse(input: &str) -> Result<BkTree, ParseError> {
+        let mut tree = BkTree::new();
+
+        for (index, line) in input.lines().enumerate() {
+            let error =
+                |offset, expected| ParseError::new(line, offset, expected).at_line(index + 1);
+            let mut fields = line.splitn(3, ' ');
+            let parent = fields.next().unwrap();
+            let distance = fields
+                .next()
+                .ok_or_else(|| error(line.len(), "a distance"))?;
+            let id = fields.next().ok_or_else(|| error(line.len(), "a box ID"))?;
+            let distance_offset = parent.len() + 1;
+
+            if index == 0 {
+                if parent != "-" || distance != "0" {
+                    return Err(error(0, "'- 0' for the root"));
+                }
+            } else {
+                let parent: usize = parent
+                    .parse()
+                    .ok()
+                    .filter(|&parent| parent >= 1 && parent <= index)
+                    .ok_or_else(|| error(0, "the line number of an earlier node"))?;
+                let distance: usize = distance
+                    .parse()
+                    .ok()
+                    .filter(|&distance| distance > 0)
+                    .ok_or_else(|| error(distance_offset, "a positive distance"))?;
+                let siblings = &mut tree.nodes[parent - 1].children;
+                if siblings.insert(distance, index).is_some() {
+                    return Err(error(
+                        distance_offset,
+                        "a distance not shared with an earlier sibling",
+                    ));
+                }
+            }
+
+            tree.nodes.push(Node {
+                id: id.to_string(),
+                children: BTreeMap::new(),
+            });
+        }
+
+        Ok(tree)
+    }
+}
+
+impl<'a> FromIterator<&'a str> for BkTree {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(ids: I) -> Self {
+        let mut tree = BkTree::new();
+        for id in ids {
+            tree.insert(id);
+        }
+        tree
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generate;
+    use crate::generate::Rng;
+
+    /// IDs of a few different lengths, so queries mix Hamming and Levenshtein distances
+    fn sample_ids(seed: u64) -> Vec<String> {
+        let rng = &mut Rng::seeded(seed);
+        let mut ids = Vec::new();
+        for len in 4..7 {
+            ids.extend(generate::day2(rng, 60, len).lines().map(String::from));
+        }
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    fn brute_force(ids: &[String], id: &str) -> Vec<(usize, String)> {
+        let mut distances: Vec<(usize, String)> = ids
+            .iter()
+            .map(|other| (diff_strings(id, other).0, other.clone()))
+            .collect();
+        distances.sort();
+        distances
+    }
+
+    fn owned(found: Vec<(usize, &str)>) -> Vec<(usize, String)> {
+        found
+            .into_iter()
+            .map(|(distance, id)| (distance, id.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn queries_match_brute_force() {
+        for seed in 0..5 {
+            let ids = sample_ids(seed);
+            let tree: BkTree = ids.iter().map(String::as_str).collect();
+            assert_eq!(tree.len(), ids.len());
+
+            for query in sample_ids(seed + 100).iter().take(20) {
+                let expected = brute_force(&ids, query);
+                assert_eq!(owned(tree.nearest(query, 5)), expected[..5].to_vec());
+
+                let within: Vec<(usize, String)> =
+                    expected.into_iter().filter(|(d, _)| *d <= 3).collect();
+                assert_eq!(owned(tree.within(query, 3)), within);
+            }
+        }
+    }
+
+    #[test]
+    fn inserts_incrementally() {
+        let mut tree = BkTree::new();
+        assert!(tree.nearest("abcde", 3).is_empty());
+        assert!(tree.insert("abcde"));
+        assert!(tree.insert("fghij"));
+        assert!(!tree.insert("abcde"));
+        assert!(tree.insert("abde"));
+
+        assert!(tree.contains("abde"));
+        assert!(!tree.contains("abd"));
+        assert_eq!(tree.nearest("abcdx", 2), vec![(1, "abcde"), (2, "abde")]);
+    }
+
+    #[test]
+    fn serializes_round_trip() {
+        let tree: BkTree = ["abcde", "fghij", "abde", "fguij", "axcye"]
+            .iter()
+            .copied()
+            .collect();
+        let serialized = tree.serialize();
+        assert_eq!(
+            serialized,
+            "- 0 abcde\n1 5 fghij\n1 1 abde\n2 1 fguij\n1 2 axcye\n"
+        );
+        assert_eq!(BkTree::parse(&serialized), Ok(tree));
+        assert_eq!(BkTree::parse(""), Ok(BkTree::new()));
+    }
+
+    #[test]
+    fn reports_malformed_trees() {
+        assert_eq!(BkTree::parse("1 0 abc").unwrap_err().line, 1);
+        let error = BkTree::parse("- 0 abc\n2 1 abd").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+        let error = BkTree::parse("- 0 abc\n1 x abd").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 3));
+        let error = BkTree::parse("- 0 abc\n1 1 abd\n1 1 xbc").unwrap_err();
+        assert_eq!(error.line, 3);
+        assert_eq!(BkTree::parse("- 0").unwrap_err().expected, "a box ID");
+    }
+}
@@ -1,54 +1,614 @@
-#[cfg(test)]
-mod test {
-    use std::collections::HashSet;
-    use std::iter;
+use crate::error::ParseError;
+use crate::solution::Solution;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+use std::io;
+use std::io::BufRead;
+use std::str::FromStr;
 
-    #[test]
-    fn part1() {
-        let freq = accumulate_frequencies(input());
-        assert_eq!(*freq.last().unwrap(), 508);
+pub struct Day1;
+
+impl Solution for Day1 {
+    const DAY: u8 = 1;
+
+    type Input = Vec<i32>;
+    type Part1 = i64;
+    type Part2 = String;
+
+    fn parse(input: &str) -> Result<Vec<i32>, ParseError> {
+        parse(input)
     }
 
-    #[test]
-    fn part2() {
-        // check our methodology works on the provided cases
-        assert_eq!(find_first_repeat(vec![1, -1]), Some(0));
-        assert_eq!(find_first_repeat(vec![3, 3, 4, -2, -4]), Some(10));
-        assert_eq!(find_first_repeat(vec![-6, 3, 8, 5, -6]), Some(5));
-        assert_eq!(find_first_repeat(vec![7, 7, -2, -7, -4]), Some(14));
+    fn part1(input: &Vec<i32>) -> i64 {
+        let mut calibrator = Calibrator::new();
+        for &change in input {
+            calibrator
+                .apply(i64::from(change))
+                .expect("frequency overflows i64");
+        }
+        calibrator.frequency()
+    }
 
-        assert_eq!(find_first_repeat(input()), Some(549));
+    fn part2(input: &Vec<i32>) -> String {
+        match find_first_repeat(input) {
+            Some(freq) => freq.to_string(),
+            None => "no repeat".to_string(),
+        }
     }
+}
+
+pub fn parse(input: &str) -> Result<Vec<i32>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            line.parse().map_err(|_| {
+                ParseError::new(line, 0, "a frequency change like '+7' or '-3'").at_line(index + 1)
+            })
+        })
+        .collect()
+}
+
+/// The frequency after each change, or where it overflowed
+pub fn accumulate_frequencies(input: &[i32]) -> Result<Vec<i64>, CalibrationError> {
+    let mut calibrator = Calibrator::new();
+    input
+        .iter()
+        .enumerate()
+        .map(|(index, &change)| apply_change(&mut calibrator, index, change))
+        .collect()
+}
+
+fn apply_change(
+    calibrator: &mut Calibrator<i64>,
+    index: usize,
+    change: i32,
+) -> Result<i64, CalibrationError> {
+    calibrator
+        .apply(i64::from(change))
+        .ok_or_else(|| CalibrationError::Overflow {
+            line: index + 1,
+            frequency: calibrator.frequency().to_string(),
+            change: format!("{:+}", change),
+        })
+}
+
+/// The first frequency reached twice while applying the changes over and over, or `None` if
+/// no frequency ever repeats.
+///
+/// After `m` full cycles the frequency at line `j` is `b[j] + m * drift`, where `b` are the
+/// frequencies seen during the first cycle and `drift` is the sum of all changes. So a later
+/// cycle can only revisit `b[i]` from a line whose frequency is congruent to it modulo the
+/// drift, and the earliest such revisit comes from the nearest `b[i]` in the drift's direction.
+pub fn find_first_repeat(changes: &[i32]) -> Option<i64> {
+    // frequencies before each change of the first cycle, so cycle m visits them again shifted by m * drift
+    let mut first_cycle = Vec::with_capacity(changes.len());
+    let mut freq = 0i64;
+    for &change in changes {
+        first_cycle.push(freq);
+        freq += i64::from(change);
+    }
+    let drift = freq;
+
+    // a repeat within the first cycle beats anything later
+    let mut seen = HashSet::new();
+    if let Some(&freq) = first_cycle.iter().find(|&&freq| !seen.insert(freq)) {
+        return Some(freq);
+    }
+    if drift == 0 {
+        // the second cycle starts back at 0
+        return first_cycle.first().copied();
+    }
+
+    let mut by_residue: HashMap<i64, Vec<(i64, usize)>> = HashMap::new();
+    for (line, &freq) in first_cycle.iter().enumerate() {
+        by_residue
+            .entry(freq.rem_euclid(drift.abs()))
+            .or_default()
+            .push((freq, line));
+    }
+
+    // (time, frequency) of the earliest revisit
+    let mut first: Option<(i64, i64)> = None;
+    for class in by_residue.values_mut() {
+        class.sort();
+        if drift < 0 {
+            class.reverse();
+        }
+        for pair in class.windows(2) {
+            let ((freq, line), (target, _)) = (pair[0], pair[1]);
+            let cycles = (target - freq) / drift;
+            let time = cycles * changes.len() as i64 + line as i64;
+            if first.is_none_or(|(best, _)| time < best) {
+                first = Some((time, target));
+            }
+        }
+    }
+    first.map(|(_, freq)| freq)
+}
+
+/// Where a frequency was reached: after applying `line` (1-based) in `cycle` (0-based), where
+/// line 0 of cycle 0 is the starting frequency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub cycle: usize,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Revisit {
+    pub frequency: i64,
+    /// in the order they were reached, so at least two
+    pub positions: Vec<Position>,
+}
 
-    fn input() -> Vec<i32> {
-        let result = std::fs::read_to_string("day1.txt").unwrap();
-        let lines = result.lines();
+/// Every frequency reached more than once within the first `cycles` cycles
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepeatReport {
+    pub cycles: usize,
+    /// ordered by when each frequency was first revisited
+    pub revisits: Vec<Revisit>,
+    /// the first repeat over any number of cycles, which may lie beyond `cycles`
+    pub first_repeat: Option<i64>,
+}
 
-        lines
-            .map(|line| line.parse().expect(&format!("trying to parse '{}'", line)))
-            .collect()
+/// Fails if a frequency overflows `i64` within those cycles
+pub fn repeat_report(changes: &[i32], cycles: usize) -> Result<RepeatReport, CalibrationError> {
+    let mut calibrator = Calibrator::new();
+    let mut positions: HashMap<i64, Vec<Position>> = HashMap::new();
+    positions
+        .entry(0)
+        .or_default()
+        .push(Position { cycle: 0, line: 0 });
+    for cycle in 0..cycles {
+        for (index, &change) in changes.iter().enumerate() {
+            let position = Position {
+                cycle,
+                line: index + 1,
+            };
+            positions
+                .entry(apply_change(&mut calibrator, index, change)?)
+                .or_default()
+                .push(position);
+        }
     }
 
-    fn accumulate_frequencies(input: Vec<i32>) -> Vec<i32> {
-        input
+    let mut revisits: Vec<Revisit> = positions
+        .into_iter()
+        .filter(|(_, positions)| positions.len() > 1)
+        .map(|(frequency, positions)| Revisit {
+            frequency,
+            positions,
+        })
+        .collect();
+    revisits.sort_by_key(|revisit| revisit.positions[1]);
+
+    Ok(RepeatReport {
+        cycles,
+        revisits,
+        first_repeat: find_first_repeat(changes),
+    })
+}
+
+impl RepeatReport {
+    pub fn to_json(&self) -> String {
+        let revisits: Vec<String> = self
+            .revisits
             .iter()
-            .scan(0, |freq, change| {
-                *freq = *freq + change;
-                return Some(*freq);
+            .map(|revisit| {
+                let positions: Vec<String> = revisit
+                    .positions
+                    .iter()
+                    .map(|p| format!(r#"{{"cycle":{},"line":{}}}"#, p.cycle, p.line))
+                    .collect();
+                format!(
+                    r#"{{"frequency":{},"count":{},"positions":[{}]}}"#,
+                    revisit.frequency,
+                    revisit.positions.len(),
+                    positions.join(",")
+                )
             })
-            .collect()
+            .collect();
+        let first_repeat = self
+            .first_repeat
+            .map_or("null".to_string(), |freq| freq.to_string());
+        format!(
+            r#"{{"cycles":{},"first_repeat":{},"revisits":[{}]}}"#,
+            self.cycles,
+            first_repeat,
+            revisits.join(",")
+        )
+    }
+}
+
+impl Display for RepeatReport {
+    /// A table with one row per revisited frequency, positions written `cycle:line`
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.first_repeat {
+            Some(freq) => writeln!(f, "first repeat: {}", freq)?,
+            None => writeln!(f, "first repeat: never")?,
+        }
+        writeln!(
+            f,
+            "{} frequencies revisited within {} cycles",
+            self.revisits.len(),
+            self.cycles
+        )?;
+        if self.revisits.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(f, "{:>12}  {:>5}  positions", "frequency", "count")?;
+        for revisit in &self.revisits {
+            let positions: Vec<String> = revisit
+                .positions
+                .iter()
+                .map(|p| format!("{}:{}", p.cycle, p.line))
+                .collect();
+            writeln!(
+                f,
+                "{:>12}  {:>5}  {}",
+                revisit.frequency,
+                revisit.positions.len(),
+                positions.join(" ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// An accumulator type for `Calibrator`
+pub trait Frequency: Copy + Default + Eq + Hash + Ord + Display + FromStr {
+    fn checked_add(self, change: Self) -> Option<Self>;
+}
+
+macro_rules! frequency {
+    ($($int:ty),*) => {
+        $(
+            impl Frequency for $int {
+                fn checked_add(self, change: Self) -> Option<Self> {
+                    <$int>::checked_add(self, change)
+                }
+            }
+        )*
+    };
+}
+
+frequency!(i32, i64, i128);
+
+/// Applies frequency changes one at a time, so arbitrarily long device logs can be streamed
+/// rather than collected.
+///
+/// A plain calibrator uses constant memory. Finding the first repeat means remembering every
+/// frequency until one comes round again, so it's only done when asked for with
+/// `with_repeat_tracking`.
+#[derive(Debug, Clone)]
+pub struct Calibrator<F: Frequency> {
+    frequency: F,
+    min: F,
+    max: F,
+    changes: usize,
+    /// every frequency so far, if tracking repeats and none has been found yet
+    seen: Option<HashSet<F>>,
+    first_repeat: Option<(usize, F)>,
+}
+
+impl<F: Frequency> Default for Calibrator<F> {
+    fn default() -> Self {
+        Calibrator {
+            frequency: F::default(),
+            min: F::default(),
+            max: F::default(),
+            changes: 0,
+            seen: None,
+            first_repeat: None,
+        }
+    }
+}
+
+impl<F: Frequency> Calibrator<F> {
+    pub fn new() -> Self {
+        Calibrator::default()
+    }
+
+    /// Also finds the first repeated frequency, at the cost of remembering every distinct
+    /// frequency until then: memory grows with each change on a log that never repeats
+    pub fn with_repeat_tracking() -> Self {
+        Calibrator {
+            seen: Some([F::default()].iter().copied().collect()),
+            ..Calibrator::default()
+        }
+    }
+
+    /// Returns the new frequency, or `None` (leaving the calibrator untouched) if it would overflow
+    pub fn apply(&mut self, change: F) -> Option<F> {
+        let frequency = self.frequency.checked_add(change)?;
+        self.frequency = frequency;
+        self.min = self.min.min(frequency);
+        self.max = self.max.max(frequency);
+
+        if let Some(seen) = &mut self.seen {
+            if !seen.insert(frequency) {
+                self.first_repeat = Some((self.changes, frequency));
+                self.seen = None;
+            }
+        }
+        self.changes += 1;
+        Some(frequency)
+    }
+
+    /// Applies one change per line until the end of `reader`
+    pub fn read(&mut self, reader: impl BufRead) -> Result<F, CalibrationError> {
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(CalibrationError::Io)?;
+            let change = line.parse().map_err(|_| {
+                CalibrationError::Parse(
+                    ParseError::new(&line, 0, "a frequency change like '+7' or '-3'")
+                        .at_line(index + 1),
+                )
+            })?;
+            self.apply(change).ok_or(CalibrationError::Overflow {
+                line: index + 1,
+                frequency: self.frequency.to_string(),
+                change: line,
+            })?;
+        }
+        Ok(self.frequency)
+    }
+
+    pub fn frequency(&self) -> F {
+        self.frequency
+    }
+
+    /// The lowest frequency so far, including the starting 0
+    pub fn min(&self) -> F {
+        self.min
+    }
+
+    pub fn max(&self) -> F {
+        self.max
+    }
+
+    /// How many changes have been applied
+    pub fn changes(&self) -> usize {
+        self.changes
+    }
+
+    /// The 0-based index of the change which first revisited a frequency, and that frequency.
+    /// Always `None` unless created `with_repeat_tracking`.
+    pub fn first_repeat(&self) -> Option<(usize, F)> {
+        self.first_repeat
+    }
+}
+
+#[derive(Debug)]
+pub enum CalibrationError {
+    Io(io::Error),
+    Parse(ParseError),
+    /// the change on `line` (1-based) took `frequency` out of range
+    Overflow {
+        line: usize,
+        frequency: String,
+        change: String,
+    },
+}
+
+impl Display for CalibrationError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            CalibrationError::Io(e) => write!(f, "couldn't read frequency changes: {}", e),
+            CalibrationError::Parse(e) => e.fmt(f),
+            CalibrationError::Overflow {
+                line,
+                frequency,
+                change,
+            } => write!(
+                f,
+                "{}: applying '{}' to frequency {} overflows",
+                line, change, frequency
+            ),
+        }
+    }
+}
+
+impl Error for CalibrationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CalibrationError::Io(e) => Some(e),
+            CalibrationError::Parse(e) => Some(e),
+            CalibrationError::Overflow { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generate;
+    use crate::generate::Rng;
+
+    #[test]
+    fn part2() {
+        // check our methodology works on the provided cases
+        assert_eq!(find_first_repeat(&[1, -1]), Some(0));
+        assert_eq!(find_first_repeat(&[3, 3, 4, -2, -4]), Some(10));
+        assert_eq!(find_first_repeat(&[-6, 3, 8, 5, -6]), Some(5));
+        assert_eq!(find_first_repeat(&[7, 7, -2, -7, -4]), Some(14));
+    }
+
+    #[test]
+    fn repeats_after_many_cycles() {
+        assert_eq!(find_first_repeat(&[1000, -999]), Some(1000));
+        assert_eq!(find_first_repeat(&[-1000, 999]), Some(-1000));
+    }
+
+    #[test]
+    fn empty_input_ends_at_0() {
+        assert_eq!(Day1::part1(&parse("").unwrap()), 0);
+        assert_eq!(Day1::part1(&vec![3, -1, 3]), 5);
+    }
+
+    #[test]
+    fn no_repeat_is_none() {
+        assert_eq!(find_first_repeat(&[]), None);
+        assert_eq!(find_first_repeat(&[1, 2]), None);
+        assert_eq!(find_first_repeat(&[3, -1, 3]), None);
     }
 
-    fn find_first_repeat(raw_input: Vec<i32>) -> Option<i32> {
-        let repeated_input = iter::repeat(raw_input).take(200).flatten().collect();
+    #[test]
+    fn matches_simulation_on_generated_input() {
+        for seed in 0..200 {
+            let changes = parse(&generate::day1(&mut Rng::seeded(seed), 8)).unwrap();
+            assert_eq!(
+                find_first_repeat(&changes),
+                simulate_first_repeat(&changes, 2_000),
+                "seed {}: {:?}",
+                seed,
+                changes
+            );
+        }
+    }
 
-        let mut seen_already = HashSet::new();
-        seen_already.insert(0);
-        for freq in accumulate_frequencies(repeated_input) {
-            if !seen_already.insert(freq) {
+    /// Applies the changes for up to `max_cycles`; with 8 changes of at most 100 the first cycle
+    /// spans at most 700, so any repeat happens within 700 cycles
+    fn simulate_first_repeat(changes: &[i32], max_cycles: usize) -> Option<i64> {
+        let mut seen = HashSet::new();
+        let mut freq = 0i64;
+        seen.insert(freq);
+        for &change in changes.iter().cycle().take(changes.len() * max_cycles) {
+            freq += i64::from(change);
+            if !seen.insert(freq) {
                 return Some(freq);
             }
         }
         None
     }
+
+    #[test]
+    fn reports_every_revisit() {
+        let report = repeat_report(&[1, -1], 2).unwrap();
+        assert_eq!(report.first_repeat, Some(0));
+        assert_eq!(
+            report.revisits,
+            vec![
+                Revisit {
+                    frequency: 0,
+                    positions: vec![
+                        Position { cycle: 0, line: 0 },
+                        Position { cycle: 0, line: 2 },
+                        Position { cycle: 1, line: 2 },
+                    ],
+                },
+                Revisit {
+                    frequency: 1,
+                    positions: vec![
+                        Position { cycle: 0, line: 1 },
+                        Position { cycle: 1, line: 1 },
+                    ],
+                },
+            ]
+        );
+        assert_eq!(
+            report.to_json(),
+            r#"{"cycles":2,"first_repeat":0,"revisits":[{"frequency":0,"count":3,"positions":[{"cycle":0,"line":0},{"cycle":0,"line":2},{"cycle":1,"line":2}]},{"frequency":1,"count":2,"positions":[{"cycle":0,"line":1},{"cycle":1,"line":1}]}]}"#
+        );
+    }
+
+    #[test]
+    fn first_revisit_is_first_repeat() {
+        let changes = parse(&crate::input::load_day(1).unwrap()).unwrap();
+        let report = repeat_report(&changes, 200).unwrap();
+        assert_eq!(Some(report.revisits[0].frequency), report.first_repeat);
+        let first_repeat = find_first_repeat(&changes).unwrap();
+        assert_eq!(report.first_repeat, Some(first_repeat));
+        assert!(report
+            .to_string()
+            .starts_with(&format!("first repeat: {}\n", first_repeat)));
+    }
+
+    #[test]
+    fn calibrator_streams_changes() {
+        let mut calibrator = Calibrator::<i64>::with_repeat_tracking();
+        assert_eq!(
+            calibrator.read("+3\n+3\n+4\n-2\n-4\n".as_bytes()).unwrap(),
+            4
+        );
+        assert_eq!((calibrator.min(), calibrator.max()), (0, 10));
+        assert_eq!(calibrator.first_repeat(), None);
+
+        assert_eq!(calibrator.read("+3\n+3\n".as_bytes()).unwrap(), 10);
+        assert_eq!(calibrator.first_repeat(), Some((6, 10)));
+        assert_eq!(calibrator.changes(), 7);
+    }
+
+    #[test]
+    fn calibrator_only_tracks_repeats_on_request() {
+        let mut calibrator = Calibrator::<i64>::new();
+        for _ in 0..1000 {
+            calibrator.apply(1);
+        }
+        calibrator.read("-5\n".as_bytes()).unwrap();
+        assert!(calibrator.seen.is_none());
+        assert_eq!(calibrator.first_repeat(), None);
+        assert_eq!(calibrator.frequency(), 995);
+    }
+
+    #[test]
+    fn calibrator_matches_part1() {
+        let input = crate::input::load_day(1).unwrap();
+        let mut calibrator = Calibrator::<i64>::new();
+        calibrator.read(input.as_bytes()).unwrap();
+        let changes = parse(&input).unwrap();
+        assert_eq!(
+            calibrator.frequency(),
+            *accumulate_frequencies(&changes).unwrap().last().unwrap()
+        );
+        assert_eq!(calibrator.frequency(), Day1::part1(&changes));
+    }
+
+    #[test]
+    fn calibrator_reports_overflow() {
+        let input = format!("+{}\n+1\n", i64::MAX);
+        let mut narrow = Calibrator::<i64>::new();
+        match narrow.read(input.as_bytes()) {
+            Err(CalibrationError::Overflow { line, .. }) => assert_eq!(line, 2),
+            other => panic!("expected overflow, got {:?}", other),
+        }
+        assert_eq!(narrow.frequency(), i64::MAX);
+
+        let mut wide = Calibrator::<i128>::new();
+        assert_eq!(
+            wide.read(input.as_bytes()).unwrap(),
+            i128::from(i64::MAX) + 1
+        );
+    }
+
+    #[test]
+    fn frequencies_pass_i32() {
+        let changes = parse(&format!("+{}\n+1\n", i32::MAX)).unwrap();
+        assert_eq!(Day1::part1(&changes), i64::from(i32::MAX) + 1);
+        assert_eq!(
+            accumulate_frequencies(&changes).unwrap(),
+            vec![i64::from(i32::MAX), i64::from(i32::MAX) + 1]
+        );
+        let report = repeat_report(&changes, 3).unwrap();
+        assert!(report.revisits.is_empty());
+    }
+
+    #[test]
+    fn calibrator_reports_bad_lines() {
+        match Calibrator::<i64>::new().read("+1\n+x\n".as_bytes()) {
+            Err(CalibrationError::Parse(error)) => assert_eq!(error.line, 2),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        let error = parse("+1\n-2\n+x").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 1));
+    }
 }
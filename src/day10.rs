@@ -1,159 +1,264 @@
-#[cfg(test)]
-mod test {
-    use core::fmt::Write;
-    use std::collections::HashSet;
-    use std::fmt::{Debug, Error, Formatter};
-    use std::fs;
-    use std::iter::Skip;
-    use std::ops::Range;
-    use std::str::FromStr;
-
-    use itertools::Itertools;
-    use regex::Regex;
-
-    type Point = (isize, isize);
-
-    type Velocity = (isize, isize); // in units per second
-
-    /// e.g. `position=< 9,  1> velocity=< 0,  2>`
-    #[derive(Debug, PartialEq, Clone)]
-    struct PointMeasurement {
-        point: Point,
-        velocity: Velocity,
+use core::fmt::Write;
+use std::fmt::{Debug, Display, Error, Formatter};
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::error::{parse_capture, parse_lines, ParseError};
+use crate::solution::Solution;
+use itertools::Itertools;
+use regex::Regex;
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    const DAY: u8 = 10;
+
+    type Input = Vec<PointMeasurement>;
+    type Part1 = String;
+    type Part2 = usize;
+
+    fn parse(input: &str) -> Result<Vec<PointMeasurement>, ParseError> {
+        parse(input)
     }
 
-    impl PointMeasurement {
-        fn step(&self, time_units: usize) -> PointMeasurement {
-            let mut cloned = self.clone();
-            cloned.step_mut(time_units);
-            cloned
-        }
+    fn part1(measurements: &Vec<PointMeasurement>) -> String {
+        find_message(measurements)
+            .map(|(_time, grid)| format!("{:?}", grid))
+            .unwrap_or_default()
+    }
 
-        fn step_mut(&mut self, time_units: usize) {
-            let (x, y) = self.point;
-            self.point.0 = x + self.velocity.0 * time_units as isize;
-            self.point.1 = y + self.velocity.1 * time_units as isize;
-        }
+    fn part2(measurements: &Vec<PointMeasurement>) -> usize {
+        find_message(measurements).map_or(0, |(time, _grid)| time)
     }
+}
 
-    fn iter_velocities(measurements: &Vec<PointMeasurement>) -> impl Iterator<Item=Grid> {
-        struct GridIterator {
-            points: Vec<PointMeasurement>,
-            time: usize,
-        }
+/// Parses every line, of which there must be at least one
+pub fn parse(input: &str) -> Result<Vec<PointMeasurement>, ParseError> {
+    let measurements = parse_lines(input)?;
+    if measurements.is_empty() {
+        return Err(ParseError::new("", 0, "at least one point"));
+    }
+    Ok(measurements)
+}
 
-        impl Iterator for GridIterator {
-            type Item = Grid;
+/// The message appears when the points are most tightly packed, so we step forward until the
+/// bounding box stops shrinking, or `None` if there are no points.
+///
+/// The box's width and height are each convex in time, so their sum only stops falling at its
+/// minimum, even for points which never converge (one point, or all moving together).
+pub fn find_message(measurements: &[PointMeasurement]) -> Option<(usize, Grid)> {
+    if measurements.is_empty() {
+        return None;
+    }
+    let spread = |grid: &Grid| {
+        let (width, height) = grid.size();
+        width + height
+    };
 
-            fn next(&mut self) -> Option<Self::Item> {
-                let grid = self.nth(self.time)?;
-                self.time += 1;
-                Some(grid)
-            }
+    let mut grids = iter_velocities(measurements).enumerate();
+    let (mut best_time, mut best_grid) = grids.next().expect("iterator is infinite");
 
-            fn nth(&mut self, mut n: usize) -> Option<Self::Item> {
-                // TODO(dfox): should this actually mutate the iterator's time field?
-                Some(Grid {
-                    points: self.points.iter()
-                        .map(|p| p.step(n).point)
-                        .collect(),
-                })
-            }
+    for (time, grid) in grids {
+        if spread(&grid) >= spread(&best_grid) {
+            break;
         }
+        best_time = time;
+        best_grid = grid;
+    }
 
-        GridIterator {
-            points: (*measurements).clone(),
-            time: 0,
-        }
+    Some((best_time, best_grid))
+}
+
+pub type Point = (isize, isize);
+
+pub type Velocity = (isize, isize); // in units per second
+
+/// e.g. `position=< 9,  1> velocity=< 0,  2>`
+#[derive(Debug, PartialEq, Clone)]
+pub struct PointMeasurement {
+    pub point: Point,
+    pub velocity: Velocity,
+}
+
+impl PointMeasurement {
+    /// Puzzle syntax with positions padded to `width` characters, e.g. 6 for real inputs
+    pub fn to_padded(&self, width: usize) -> String {
+        format!(
+            "position=<{:>width$}, {:>width$}> velocity=<{:>2}, {:>2}>",
+            self.point.0,
+            self.point.1,
+            self.velocity.0,
+            self.velocity.1,
+            width = width
+        )
     }
 
-    impl FromStr for PointMeasurement {
-        type Err = ();
+    pub fn step(&self, time_units: usize) -> PointMeasurement {
+        let mut cloned = self.clone();
+        cloned.step_mut(time_units);
+        cloned
+    }
 
-        fn from_str(s: &str) -> Result<Self, Self::Err> {
-            lazy_static! {
-                static ref RE: Regex = Regex::new(r"^position=< *(?P<point_x>-?\d+), *(?P<point_y>-?\d+)> velocity=< *(?P<velocity_x>-?\d+), *(?P<velocity_y>-?\d+)>$").unwrap();
-            }
+    pub fn step_mut(&mut self, time_units: usize) {
+        let (x, y) = self.point;
+        self.point.0 = x + self.velocity.0 * time_units as isize;
+        self.point.1 = y + self.velocity.1 * time_units as isize;
+    }
+}
 
-            let captures = RE
-                .captures(s)
-                .expect(&("Regex should match: ".to_string() + s));
-
-            let point_x: isize = captures.name("point_x").unwrap().as_str().parse().unwrap();
-            let point_y: isize = captures.name("point_y").unwrap().as_str().parse().unwrap();
-            let velocity_x: isize = captures
-                .name("velocity_x")
-                .unwrap()
-                .as_str()
-                .parse()
-                .unwrap();
-            let velocity_y: isize = captures
-                .name("velocity_y")
-                .unwrap()
-                .as_str()
-                .parse()
-                .unwrap();
+pub fn iter_velocities(measurements: &[PointMeasurement]) -> impl Iterator<Item = Grid> {
+    struct GridIterator {
+        points: Vec<PointMeasurement>,
+        time: usize,
+    }
+
+    impl Iterator for GridIterator {
+        type Item = Grid;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let grid = self.nth(self.time)?;
+            self.time += 1;
+            Some(grid)
+        }
 
-            Ok(PointMeasurement {
-                point: (point_x, point_y),
-                velocity: (velocity_x, velocity_y),
+        fn nth(&mut self, n: usize) -> Option<Self::Item> {
+            // TODO(dfox): should this actually mutate the iterator's time field?
+            Some(Grid {
+                points: self.points.iter().map(|p| p.step(n).point).collect(),
             })
         }
     }
 
-    struct Grid {
-        points: Vec<Point>,
+    GridIterator {
+        points: measurements.to_vec(),
+        time: 0,
     }
+}
 
-    impl Grid {
-        fn bounds(&self) -> (Range<isize>, Range<isize>) {
-            let (min_x, max_x) = self
-                .points
-                .iter()
-                .map(|&point| point.0)
-                .minmax()
-                .into_option()
-                .expect("Vec must not be empty");
-            let (min_y, max_y) = self
-                .points
-                .iter()
-                .map(|&point| point.1)
-                .minmax()
-                .into_option()
-                .expect("Vec must not be empty");
-
-            return (min_x..max_x, min_y..max_y);
-        }
+impl Display for PointMeasurement {
+    /// Laid out like the example, with positions padded to 2 characters
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.pad(&self.to_padded(2))
+    }
+}
 
-        fn size(&self) -> (usize, usize) {
-            let (xs, ys) = self.bounds();
-            ((xs.end - xs.start) as usize, (ys.end - ys.start) as usize)
-        }
+impl FromStr for PointMeasurement {
+    type Err = ParseError;
 
-        fn area(&self) -> usize {
-            let (width, height) = self.size();
-            width * height
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"^position=< *(?P<point_x>-?\d+), *(?P<point_y>-?\d+)> velocity=< *(?P<velocity_x>-?\d+), *(?P<velocity_y>-?\d+)>$").unwrap();
         }
+
+        let captures = RE.captures(s).ok_or_else(|| {
+            ParseError::new(
+                s,
+                0,
+                "a measurement like 'position=< 9,  1> velocity=< 0,  2>'",
+            )
+        })?;
+        let number = |name| parse_capture(s, &captures, name, "a number that fits in isize");
+
+        let point_x: isize = number("point_x")?;
+        let point_y: isize = number("point_y")?;
+        let velocity_x: isize = number("velocity_x")?;
+        let velocity_y: isize = number("velocity_y")?;
+
+        Ok(PointMeasurement {
+            point: (point_x, point_y),
+            velocity: (velocity_x, velocity_y),
+        })
     }
+}
 
-    impl Debug for Grid {
-        fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-            let (xs, ys) = self.bounds();
-
-            for y in ys {
-                for x in xs.clone() {
-                    let current: Point = (x, y);
-                    if self.points.contains(&current) {
-                        f.write_char('#')?
-                    } else {
-                        f.write_char('.')?
-                    }
-                }
+pub struct Grid {
+    pub points: Vec<Point>,
+}
+
+impl Grid {
+    pub fn bounds(&self) -> (Range<isize>, Range<isize>) {
+        let (min_x, max_x) = self
+            .points
+            .iter()
+            .map(|&point| point.0)
+            .minmax()
+            .into_option()
+            .expect("Vec must not be empty");
+        let (min_y, max_y) = self
+            .points
+            .iter()
+            .map(|&point| point.1)
+            .minmax()
+            .into_option()
+            .expect("Vec must not be empty");
+
+        (min_x..max_x, min_y..max_y)
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        let (xs, ys) = self.bounds();
+        ((xs.end - xs.start) as usize, (ys.end - ys.start) as usize)
+    }
+
+    pub fn area(&self) -> usize {
+        let (width, height) = self.size();
+        width * height
+    }
+}
 
-                f.write_char('\n')?
+impl Debug for Grid {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let (xs, ys) = self.bounds();
+
+        for y in ys.start..=ys.end {
+            for x in xs.start..=xs.end {
+                let current: Point = (x, y);
+                if self.points.contains(&current) {
+                    f.write_char('#')?
+                } else {
+                    f.write_char('.')?
+                }
             }
 
-            Ok(())
+            f.write_char('\n')?
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generate::Rng;
+    use crate::input;
+
+    #[test]
+    fn display_round_trips() {
+        let example = "position=< 9,  1> velocity=< 0,  2>";
+        let measurement = PointMeasurement::from_str(example).unwrap();
+        assert_eq!(measurement.to_string(), example);
+        assert_eq!(format!("{:>40}", measurement), format!("{:>40}", example));
+        for line in input::load_day(10).unwrap().lines() {
+            let measurement = PointMeasurement::from_str(line).unwrap();
+            assert_eq!(measurement.to_padded(6), line);
+        }
+
+        let rng = &mut Rng::seeded(23);
+        let number = |rng: &mut Rng| rng.range(-100_000..=100_000) as isize;
+        for _ in 0..100 {
+            let measurement = PointMeasurement {
+                point: (number(rng), number(rng)),
+                velocity: (number(rng), number(rng)),
+            };
+            assert_eq!(
+                PointMeasurement::from_str(&measurement.to_padded(6)),
+                Ok(measurement.clone())
+            );
+            assert_eq!(
+                PointMeasurement::from_str(&measurement.to_string()),
+                Ok(measurement)
+            );
         }
     }
 
@@ -182,6 +287,44 @@ mod test {
         )
     }
 
+    #[test]
+    fn malformed_lines_are_errors() {
+        let error = PointMeasurement::from_str("position=< 9,  1>").unwrap_err();
+        assert_eq!(error.column, 1);
+
+        let error =
+            PointMeasurement::from_str("position=< 9,  1> velocity=< 99999999999999999999,  2>")
+                .unwrap_err();
+        assert_eq!(error.column, 30);
+    }
+
+    #[test]
+    fn finds_sample_message() {
+        let (time, grid) = find_message(&sample_data()).unwrap();
+        assert_eq!(time, 3);
+        assert_eq!(grid.size(), (9, 7));
+    }
+
+    #[test]
+    fn stops_when_points_never_converge() {
+        let single = parse("position=< 1,  1> velocity=< 0,  0>").unwrap();
+        assert_eq!(find_message(&single).unwrap().0, 0);
+
+        let together =
+            parse("position=< 1,  1> velocity=< 3, -2>\nposition=< 5,  2> velocity=< 3, -2>")
+                .unwrap();
+        assert_eq!(find_message(&together).unwrap().0, 0);
+
+        let moving = parse("position=< 7,  7> velocity=< 1,  1>").unwrap();
+        assert_eq!(find_message(&moving).unwrap().0, 0);
+    }
+
+    #[test]
+    fn empty_input_is_an_error() {
+        assert_eq!(parse("").unwrap_err().expected, "at least one point");
+        assert!(find_message(&[]).is_none());
+    }
+
     #[test]
     fn render_sample_data() {
         iter_velocities(&sample_data()).take(4).for_each(|g| {
@@ -228,17 +371,13 @@ position=<-3,  6> velocity=< 2, -1>"#;
     }
 
     fn real_data() -> Vec<PointMeasurement> {
-        fs::read_to_string("day10.txt")
-            .unwrap()
-            .lines()
-            .map(|s| s.parse().unwrap())
-            .collect()
+        parse(&input::load_day(10).unwrap()).unwrap()
     }
 
     #[test]
     fn render_real_data() {
         let max_iterations = 20_000;
-        let mut last_area = usize::max_value();
+        let mut last_area = usize::MAX;
         let mut smallest_grid = None;
         let real_data = real_data();
 
@@ -254,7 +393,7 @@ position=<-3,  6> velocity=< 2, -1>"#;
         dbg!(&samples);
         samples.truncate(2);
         samples.sort_by_key(|&(time, _)| time);
-        let start_time = samples[0].0;
+        let _start_time = samples[0].0;
 
         // TODO(dfox): use this start_time to avoid granular searches early on in time
 
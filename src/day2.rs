@@ -1,147 +1,530 @@
-#[cfg(test)]
-mod test {
-    use itertools::Itertools;
-    use std::collections::HashSet;
-    use std::fs;
-    use std::iter::FromIterator;
-    use std::string::String;
+use crate::error::ParseError;
+use crate::solution::Solution;
+use itertools::Itertools;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::iter::FromIterator;
+use std::string::String;
+use unicode_segmentation::UnicodeSegmentation;
 
-    #[test]
-    fn part1() {
-        let checksum: Checksum = fs::read_to_string("day2.txt")
-            .unwrap()
-            .lines()
-            .map(ChecksumEntry::from)
-            .collect();
-        assert_eq!(checksum, Checksum(5928));
-    }
+pub struct Day2;
 
-    fn find_strings_differing_by_1(input: Vec<&str>) -> HashSet<String> {
-        let mut results = HashSet::new();
+impl Solution for Day2 {
+    const DAY: u8 = 2;
 
-        for (left, right) in input.iter().cartesian_product(input.iter()) {
-            let (num, matches) = diff_strings(left, right);
-            if num == 1 {
-                results.insert(matches);
-            }
-        }
+    type Input = Vec<String>;
+    type Part1 = u64;
+    type Part2 = String;
 
-        results
+    fn parse(input: &str) -> Result<Vec<String>, ParseError> {
+        Ok(input.lines().map(String::from).collect())
     }
 
-    #[test]
-    fn part2() {
-        assert_eq!(
-            find_strings_differing_by_1(vec!(
-                "abcde", "fghij", "klmno", "pqrst", "fguij", "axcye", "wvxyz"
-            )),
-            HashSet::from_iter(vec!("fgij".to_owned()))
-        );
+    fn part1(input: &Vec<String>) -> u64 {
+        ChecksumBuilder::new()
+            .checksum(input.iter().map(String::as_str))
+            .product()
+            .expect("checksum overflows u64")
+    }
 
-        assert_eq!(
-            find_strings_differing_by_1(fs::read_to_string("day2.txt").unwrap().lines().collect()),
-            HashSet::from_iter(vec!("bqlporuexkwzyabnmgjqctvfs".to_owned()))
-        )
+    fn part2(input: &Vec<String>) -> String {
+        find_strings_differing_by_1(input.iter().map(String::as_str).collect())
+            .into_iter()
+            .sorted()
+            .join(",")
+    }
+}
+
+pub fn find_strings_differing_by_1(input: Vec<&str>) -> HashSet<String> {
+    pairs_differing_by_1(&input)
+        .into_iter()
+        .map(|(left, right)| diff_strings(input[left], input[right]).1)
+        .collect()
+}
+
+/// Indices `(i, j)`, `i < j`, of every pair of IDs with the same length differing at exactly one
+/// position.
+///
+/// Rather than comparing every pair, each distinct ID is hashed once per position with that
+/// position masked out, so IDs differing only there land in the same bucket. Within a bucket only
+/// IDs with different characters at the masked position are compared, so copies of an ID cost
+/// nothing beyond the pairs they're part of.
+pub fn pairs_differing_by_1(ids: &[&str]) -> Vec<(usize, usize)> {
+    const BASE: u64 = 0x100_0000_01b3;
+
+    // each distinct ID, and the indices of its copies
+    let mut distinct: HashMap<&str, usize> = HashMap::new();
+    let mut copies: Vec<Vec<usize>> = Vec::new();
+    let mut chars: Vec<Vec<char>> = Vec::new();
+    for (index, &id) in ids.iter().enumerate() {
+        let next = chars.len();
+        let d = *distinct.entry(id).or_insert(next);
+        if d == next {
+            chars.push(id.chars().collect());
+            copies.push(Vec::new());
+        }
+        copies[d].push(index);
     }
 
-    fn diff_strings(left: &str, right: &str) -> (usize, String) {
-        assert_eq!(left.len(), right.len());
+    let hashes: Vec<u64> = chars
+        .iter()
+        .map(|id| {
+            id.iter().fold(0u64, |hash, &c| {
+                hash.wrapping_mul(BASE).wrapping_add(c as u64)
+            })
+        })
+        .collect();
+    let max_len = chars.iter().map(Vec::len).max().unwrap_or(0);
+    // powers[k] is the weight of the character k places from the end
+    let powers: Vec<u64> = (0..max_len)
+        .scan(1u64, |power, _| {
+            let current = *power;
+            *power = power.wrapping_mul(BASE);
+            Some(current)
+        })
+        .collect();
 
-        let matching: Vec<u8> = left
-            .as_bytes()
+    let mut pairs = Vec::new();
+    let mut buckets: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+    for position in 0..max_len {
+        buckets.clear();
+        for (d, id) in chars
             .iter()
-            .zip(right.as_bytes())
-            .filter(|(&left_byte, &right_byte)| left_byte == right_byte)
-            .map(|(&left_byte, _)| left_byte)
-            .collect();
+            .enumerate()
+            .filter(|(_, id)| position < id.len())
+        {
+            let weight = powers[id.len() - 1 - position];
+            let masked = hashes[d].wrapping_sub((id[position] as u64).wrapping_mul(weight));
+            buckets.entry((id.len(), masked)).or_default().push(d);
+        }
 
-        let differing_chars = left.len() - matching.len();
-        let matching_str: &str = std::str::from_utf8(&matching).unwrap();
+        for bucket in buckets.values().filter(|bucket| bucket.len() > 1) {
+            let by_char = bucket.iter().into_group_map_by(|&&d| chars[d][position]);
+            for (lefts, rights) in by_char.values().tuple_combinations() {
+                for (&&left, &&right) in lefts.iter().cartesian_product(rights) {
+                    // the hashes only say the rest probably matches
+                    let (left_id, right_id) = (&chars[left], &chars[right]);
+                    if left_id
+                        .iter()
+                        .zip(right_id)
+                        .enumerate()
+                        .all(|(i, (l, r))| i == position || l == r)
+                    {
+                        let copy_pairs = copies[left].iter().cartesian_product(&copies[right]);
+                        pairs.extend(copy_pairs.map(|(&i, &j)| (i.min(j), i.max(j))));
+                    }
+                }
+            }
+        }
+    }
 
-        (differing_chars, matching_str.to_owned())
+    pairs.sort();
+    pairs
+}
+
+/// The number of differing characters and the characters in common: position by position for
+/// IDs of the same length, or along the cheapest alignment otherwise
+pub fn diff_strings(left: &str, right: &str) -> (usize, String) {
+    let alignment = align(left, right);
+    (alignment.distance, alignment.common())
+}
+
+/// How to turn one ID into another, one character at a time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edit {
+    Match(char),
+    /// left character, right character
+    Substitute(char, char),
+    /// only in the left ID
+    Delete(char),
+    /// only in the right ID
+    Insert(char),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alignment {
+    pub distance: usize,
+    pub edits: Vec<Edit>,
+}
+
+impl Alignment {
+    pub fn common(&self) -> String {
+        self.edits
+            .iter()
+            .filter_map(|edit| match edit {
+                Edit::Match(c) => Some(c),
+                _ => None,
+            })
+            .collect()
     }
+}
 
-    #[test]
-    fn count_differing_characters_samples() {
-        assert_eq!(diff_strings("fghij", "fguij"), (1, "fgij".to_owned()));
-        assert_eq!(diff_strings("abcde", "axcye"), (2, "ace".to_owned()));
+impl Display for Alignment {
+    /// Both IDs on separate lines, with `-` where a character was inserted or deleted
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let (left, right): (String, String) = self
+            .edits
+            .iter()
+            .map(|edit| match *edit {
+                Edit::Match(c) => (c, c),
+                Edit::Substitute(l, r) => (l, r),
+                Edit::Delete(c) => (c, '-'),
+                Edit::Insert(c) => ('-', c),
+            })
+            .unzip();
+        write!(f, "{}\n{}", left, right)
     }
+}
 
-    #[derive(Debug, PartialEq)]
-    struct Checksum(u64);
+/// Hamming distance for IDs of the same length, Levenshtein distance otherwise
+pub fn align(left: &str, right: &str) -> Alignment {
+    align_within(left, right, usize::MAX).unwrap()
+}
 
-    impl FromIterator<ChecksumEntry> for Checksum {
-        fn from_iter<I>(iter: I) -> Self
-        where
-            I: IntoIterator<Item = ChecksumEntry>,
-        {
-            let mut twos: u64 = 0;
-            let mut threes: u64 = 0;
-            for entry in iter {
-                twos += if entry.exactly_2_of_any { 1 } else { 0 };
-                threes += if entry.exactly_3_of_any { 1 } else { 0 };
+/// Like `align`, but gives up as soon as the distance must exceed `k`
+pub fn align_within(left: &str, right: &str, k: usize) -> Option<Alignment> {
+    let left: Vec<char> = left.chars().collect();
+    let right: Vec<char> = right.chars().collect();
+
+    if left.len() == right.len() {
+        let mut distance = 0;
+        let mut edits = Vec::with_capacity(left.len());
+        for (&l, &r) in left.iter().zip(&right) {
+            if l == r {
+                edits.push(Edit::Match(l));
+            } else {
+                distance += 1;
+                if distance > k {
+                    return None;
+                }
+                edits.push(Edit::Substitute(l, r));
             }
-            Checksum(twos * threes)
         }
+        Some(Alignment { distance, edits })
+    } else if left.len().abs_diff(right.len()) > k {
+        None
+    } else {
+        levenshtein_within(&left, &right, k)
     }
+}
 
-    #[derive(Debug, PartialEq, Copy, Clone)]
-    struct ChecksumEntry {
-        pub exactly_2_of_any: bool,
-        pub exactly_3_of_any: bool,
+/// Edit distance between IDs of any lengths, which unlike `diff_strings` never counts a
+/// shift as several substitutions
+pub fn levenshtein(left: &str, right: &str) -> usize {
+    let left: Vec<char> = left.chars().collect();
+    let right: Vec<char> = right.chars().collect();
+    levenshtein_within(&left, &right, usize::MAX)
+        .unwrap()
+        .distance
+}
+
+fn levenshtein_within(left: &[char], right: &[char], k: usize) -> Option<Alignment> {
+    // costs[i][j] turns the first i characters of left into the first j of right
+    let mut costs = vec![vec![0; right.len() + 1]; left.len() + 1];
+    for (j, cost) in costs[0].iter_mut().enumerate() {
+        *cost = j;
+    }
+    for i in 1..=left.len() {
+        costs[i][0] = i;
+        for j in 1..=right.len() {
+            let substitution = usize::from(left[i - 1] != right[j - 1]);
+            costs[i][j] = (costs[i - 1][j - 1] + substitution)
+                .min(costs[i - 1][j] + 1)
+                .min(costs[i][j - 1] + 1);
+        }
+        // every alignment passes through this row, so none can come in under k any more
+        if costs[i].iter().min().unwrap() > &k {
+            return None;
+        }
     }
 
-    impl From<&str> for ChecksumEntry {
-        fn from(string: &str) -> Self {
-            let mut char_counts: [u8; 256] = [0; 256];
+    let distance = costs[left.len()][right.len()];
+    if distance > k {
+        return None;
+    }
 
-            for &byte in string.as_bytes() {
-                let index: usize = byte as usize;
-                char_counts[index] += 1
+    // walk back from the end, preferring matches and substitutions
+    let mut edits = Vec::with_capacity(left.len().max(right.len()));
+    let (mut i, mut j) = (left.len(), right.len());
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 {
+            let substitution = usize::from(left[i - 1] != right[j - 1]);
+            if costs[i][j] == costs[i - 1][j - 1] + substitution {
+                edits.push(if substitution == 0 {
+                    Edit::Match(left[i - 1])
+                } else {
+                    Edit::Substitute(left[i - 1], right[j - 1])
+                });
+                i -= 1;
+                j -= 1;
+                continue;
             }
+        }
+        if i > 0 && costs[i][j] == costs[i - 1][j] + 1 {
+            edits.push(Edit::Delete(left[i - 1]));
+            i -= 1;
+        } else {
+            edits.push(Edit::Insert(right[j - 1]));
+            j -= 1;
+        }
+    }
+    edits.reverse();
+
+    Some(Alignment { distance, edits })
+}
 
-            let mut two = false;
-            let mut three = false;
+/// A pair of IDs, by index, within the distance asked for
+#[derive(Debug, Clone, PartialEq)]
+pub struct Similar {
+    pub left: usize,
+    pub right: usize,
+    pub alignment: Alignment,
+}
 
-            for &count in char_counts.iter() {
-                match count {
-                    2 => two = true,
-                    3 => three = true,
-                    _ => {}
-                }
-            }
-            ChecksumEntry {
-                exactly_2_of_any: two,
-                exactly_3_of_any: three,
+/// Every pair of IDs (`left < right`) within distance `k`, as measured by `align`
+pub fn pairs_within(ids: &[&str], k: usize) -> Vec<Similar> {
+    (0..ids.len())
+        .tuple_combinations()
+        .filter_map(|(left, right)| {
+            align_within(ids[left], ids[right], k).map(|alignment| Similar {
+                left,
+                right,
+                alignment,
+            })
+        })
+        .collect()
+}
+
+/// Whether letters are Unicode scalar values, or grapheme clusters as a reader would see them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Letters {
+    Chars,
+    Graphemes,
+}
+
+/// Which letter counts a checksum tracks, and what counts as a letter
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChecksumBuilder {
+    counts: BTreeSet<usize>,
+    letters: Letters,
+}
+
+impl Default for ChecksumBuilder {
+    /// The puzzle's checksum: exactly 2 and exactly 3 of any character
+    fn default() -> Self {
+        ChecksumBuilder {
+            counts: [2, 3].iter().copied().collect(),
+            letters: Letters::Chars,
+        }
+    }
+}
+
+impl ChecksumBuilder {
+    pub fn new() -> Self {
+        ChecksumBuilder::default()
+    }
+
+    /// Replaces the tracked counts; 0 is ignored, as no letter occurs exactly 0 times
+    pub fn counts(mut self, counts: impl IntoIterator<Item = usize>) -> Self {
+        self.counts = counts.into_iter().filter(|&n| n > 0).collect();
+        self
+    }
+
+    pub fn letters(mut self, letters: Letters) -> Self {
+        self.letters = letters;
+        self
+    }
+
+    pub fn entry(&self, id: &str) -> ChecksumEntry {
+        let mut letter_counts: HashMap<&str, usize> = HashMap::new();
+        let letters: Box<dyn Iterator<Item = &str>> = match self.letters {
+            Letters::Chars => Box::new(id.char_indices().map(|(i, c)| &id[i..i + c.len_utf8()])),
+            Letters::Graphemes => Box::new(id.graphemes(true)),
+        };
+        for letter in letters {
+            *letter_counts.entry(letter).or_default() += 1;
+        }
+
+        ChecksumEntry(
+            self.counts
+                .iter()
+                .map(|&n| (n, letter_counts.values().any(|&count| count == n)))
+                .collect(),
+        )
+    }
+
+    /// Every tracked count gets a tally, even with no IDs, so an empty list checksums to 0
+    pub fn checksum<'a>(&self, ids: impl IntoIterator<Item = &'a str>) -> Checksum {
+        let none = ChecksumEntry(self.counts.iter().map(|&n| (n, false)).collect());
+        std::iter::once(none)
+            .chain(ids.into_iter().map(|id| self.entry(id)))
+            .collect()
+    }
+}
+
+/// For each tracked count, whether some letter of one ID occurs exactly that many times
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChecksumEntry(pub BTreeMap<usize, bool>);
+
+impl ChecksumEntry {
+    pub fn has_exactly(&self, n: usize) -> bool {
+        self.0.get(&n) == Some(&true)
+    }
+}
+
+impl From<&str> for ChecksumEntry {
+    fn from(id: &str) -> Self {
+        ChecksumBuilder::default().entry(id)
+    }
+}
+
+/// For each tracked count, how many IDs have some letter exactly that many times
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Checksum {
+    pub tallies: BTreeMap<usize, u64>,
+}
+
+impl Checksum {
+    pub fn tally(&self, n: usize) -> u64 {
+        self.tallies.get(&n).copied().unwrap_or(0)
+    }
+
+    /// The checksum itself, or `None` if it doesn't fit in a `u64`
+    pub fn product(&self) -> Option<u64> {
+        self.tallies
+            .values()
+            .try_fold(1u64, |product, &tally| product.checked_mul(tally))
+    }
+}
+
+impl FromIterator<ChecksumEntry> for Checksum {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = ChecksumEntry>,
+    {
+        let mut checksum = Checksum::default();
+        for entry in iter {
+            for (n, matched) in entry.0 {
+                *checksum.tallies.entry(n).or_default() += u64::from(matched);
             }
         }
+        checksum
     }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generate;
+    use crate::generate::Rng;
 
     #[test]
-    fn sample_testcases() {
-        assert_eq!(
-            ChecksumEntry::from("abcdef"),
-            ChecksumEntry {
-                exactly_2_of_any: false,
-                exactly_3_of_any: false
-            }
-        );
+    fn indexed_search_matches_all_pairs() {
+        for seed in 0..20 {
+            let input = generate::day2(&mut Rng::seeded(seed), 200, 6);
+            let mut ids: Vec<&str> = input.lines().collect();
+            ids.extend(["abc", "abd", "abd", "xbd"].iter());
+
+            let expected: Vec<(usize, usize)> = (0..ids.len())
+                .tuple_combinations()
+                .filter(|&(i, j)| {
+                    ids[i].len() == ids[j].len() && diff_strings(ids[i], ids[j]).0 == 1
+                })
+                .collect();
+            assert_eq!(pairs_differing_by_1(&ids), expected, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn copies_of_an_id_are_cheap() {
+        let mut ids = vec!["abcdefghijklmnopqrstuvwxyz"; 20_000];
+        ids.insert(5, "abcdefghijklmnopqrstuvwxyA");
+        ids.push("abcdefghijklmnopqrstuvwxyB");
+
+        let pairs = pairs_differing_by_1(&ids);
+        assert_eq!(pairs.len(), 2 * 20_000 + 1);
+        assert_eq!(pairs[..4], [(0, 5), (0, 20_001), (1, 5), (1, 20_001)]);
+        assert!(pairs.contains(&(5, 20_001)));
+        assert_eq!(pairs.last(), Some(&(20_000, 20_001)));
+    }
+
+    #[test]
+    fn hamming_distance_for_equal_lengths() {
+        let alignment = align_within("abcde", "axcye", 2).unwrap();
+        assert_eq!(alignment.distance, 2);
+        assert_eq!(alignment.common(), "ace");
+        assert_eq!(alignment.to_string(), "abcde\naxcye");
+        assert_eq!(align_within("abcde", "axcye", 1), None);
+
+        // even where shifting would be cheaper
+        assert_eq!(align("abcd", "bcda").distance, 4);
+    }
+
+    #[test]
+    fn levenshtein_distance_for_unequal_lengths() {
+        let alignment = align("abcde", "abde");
+        assert_eq!(alignment.distance, 1);
+        assert_eq!(alignment.common(), "abde");
+        assert_eq!(alignment.to_string(), "abcde\nab-de");
+
+        let alignment = align("kitten", "sitting");
+        assert_eq!(alignment.distance, 3);
+        assert_eq!(alignment.common(), "ittn");
+        assert_eq!(align_within("kitten", "sitting", 2), None);
+        assert_eq!(align_within("a", "abcd", 2), None);
+
+        assert_eq!(diff_strings("", "abc"), (3, String::new()));
+        assert_eq!(levenshtein("abcd", "bcda"), 2);
+    }
+
+    #[test]
+    fn finds_pairs_within_k() {
+        let ids = ["abcde", "abde", "fghij", "fguij", "axcye", "fgij"];
+        let pairs: Vec<(usize, usize, usize)> = pairs_within(&ids, 1)
+            .iter()
+            .map(|similar| (similar.left, similar.right, similar.alignment.distance))
+            .collect();
+        assert_eq!(pairs, vec![(0, 1, 1), (2, 3, 1), (2, 5, 1), (3, 5, 1)]);
+
+        assert_eq!(pairs_within(&ids, 2).len(), 5);
+    }
+
+    #[test]
+    fn ignores_ids_of_other_lengths() {
         assert_eq!(
-            ChecksumEntry::from("bababc"),
-            ChecksumEntry {
-                exactly_2_of_any: true,
-                exactly_3_of_any: true
-            }
+            pairs_differing_by_1(&["abc", "abcd", "abd", "é"]),
+            vec![(0, 2)]
         );
+    }
+
+    #[test]
+    fn part2() {
         assert_eq!(
-            ChecksumEntry::from("abbcde"),
-            ChecksumEntry {
-                exactly_2_of_any: true,
-                exactly_3_of_any: false
-            }
+            find_strings_differing_by_1(vec!(
+                "abcde", "fghij", "klmno", "pqrst", "fguij", "axcye", "wvxyz"
+            )),
+            HashSet::from_iter(vec!("fgij".to_owned()))
         );
     }
 
+    #[test]
+    fn count_differing_characters_samples() {
+        assert_eq!(diff_strings("fghij", "fguij"), (1, "fgij".to_owned()));
+        assert_eq!(diff_strings("abcde", "axcye"), (2, "ace".to_owned()));
+    }
+
+    #[test]
+    fn sample_testcases() {
+        let entry = |id| {
+            let entry = ChecksumEntry::from(id);
+            (entry.has_exactly(2), entry.has_exactly(3))
+        };
+        assert_eq!(entry("abcdef"), (false, false));
+        assert_eq!(entry("bababc"), (true, true));
+        assert_eq!(entry("abbcde"), (true, false));
+    }
+
     #[test]
     fn sample_testcases_2() {
         let input = [
@@ -151,6 +534,42 @@ mod test {
             .iter()
             .map(|&line| ChecksumEntry::from(line))
             .collect();
-        assert_eq!(foo, Checksum(12));
+        assert_eq!(foo.tally(2), 4);
+        assert_eq!(foo.tally(3), 3);
+        assert_eq!(foo.product(), Some(12));
+    }
+
+    #[test]
+    fn tracks_any_counts() {
+        let checksum = ChecksumBuilder::new()
+            .counts(vec![1, 3, 300])
+            .checksum(vec!["abcccd", "aabb", &"x".repeat(300)]);
+        assert_eq!(checksum.tally(1), 1);
+        assert_eq!(checksum.tally(3), 1);
+        assert_eq!(checksum.tally(300), 1);
+        assert_eq!(checksum.tally(2), 0);
+        assert_eq!(checksum.product(), Some(1));
+    }
+
+    #[test]
+    fn no_ids_checksum_to_0() {
+        let checksum = ChecksumBuilder::new().checksum(vec![]);
+        assert_eq!(checksum.tally(2), 0);
+        assert_eq!(checksum.product(), Some(0));
+        assert_eq!(Day2::part1(&Day2::parse("").unwrap()), 0);
+    }
+
+    #[test]
+    fn counts_unicode_letters() {
+        // every byte of 日 and 本 begins 0xE6, which a byte table would count 3 times
+        let entry = ChecksumBuilder::new().entry("日日本");
+        assert!(entry.has_exactly(2));
+        assert!(!entry.has_exactly(3));
+
+        // é written as e plus a combining accent is one grapheme but two chars
+        let id = "e\u{301}e";
+        assert!(ChecksumBuilder::new().entry(id).has_exactly(2));
+        let graphemes = ChecksumBuilder::new().letters(Letters::Graphemes);
+        assert!(!graphemes.entry(id).has_exactly(2));
     }
 }
@@ -1,97 +1,300 @@
-#[cfg(test)]
-mod test {
-    use itertools::Itertools;
-    use multiset::HashMultiSet;
-    use regex::Regex;
-    use std::collections::HashSet;
-    use std::fs;
-    use std::num::ParseIntError;
-    use std::str::FromStr;
+use crate::error::{parse_capture, parse_lines, ParseError};
+use crate::solution::Solution;
+use itertools::Itertools;
+use multiset::HashMultiSet;
+use regex::Regex;
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::str::FromStr;
 
-    type Square = (usize, usize);
+pub mod fabric;
+pub mod index;
+pub mod packing;
+pub mod render;
 
-    #[test]
-    fn part1() {
-        assert_eq!(Claim::contended_squares(&input()).len(), 117505);
+pub use fabric::Fabric;
+pub use index::ClaimIndex;
+pub use packing::Planner;
+pub use render::Renderer;
+
+pub type Square = (usize, usize);
+
+pub struct Day3;
+
+impl Solution for Day3 {
+    const DAY: u8 = 3;
+
+    type Input = Vec<Claim>;
+    type Part1 = usize;
+    type Part2 = String;
+
+    fn parse(input: &str) -> Result<Vec<Claim>, ParseError> {
+        parse(input)
     }
 
-    #[test]
-    fn part2() {
-        let uncontended: Vec<Claim> = Claim::find_uncontended_claims(&input());
-        assert_eq!(uncontended.len(), 1);
-        assert_eq!(uncontended[0].id, 1254);
-    }
-
-    #[derive(Debug, PartialEq, Copy, Clone)]
-    struct Claim {
-        pub id: usize,
-        offset_left: usize,
-        offset_top: usize,
-        width: usize,
-        height: usize,
-    }
-
-    impl Claim {
-        pub fn squares(&self) -> impl Iterator<Item = Square> {
-            let xs = self.offset_left..(self.offset_left + self.width);
-            let ys = self.offset_top..(self.offset_top + self.height);
-            xs.cartesian_product(ys)
-        }
+    fn part1(claims: &Vec<Claim>) -> usize {
+        Claim::contended_area(claims)
+    }
+
+    fn part2(claims: &Vec<Claim>) -> String {
+        Claim::find_uncontended_claims(claims)
+            .iter()
+            .map(|claim| claim.id)
+            .join(",")
+    }
+}
+
+pub fn parse(input: &str) -> Result<Vec<Claim>, ParseError> {
+    parse_lines(input)
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Claim {
+    pub id: usize,
+    pub offset_left: usize,
+    pub offset_top: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Claim {
+    pub fn squares(&self) -> impl Iterator<Item = Square> {
+        let xs = self.offset_left..(self.offset_left + self.width);
+        let ys = self.offset_top..(self.offset_top + self.height);
+        xs.cartesian_product(ys)
+    }
+
+    /// Just past the right-hand edge
+    pub fn right(&self) -> usize {
+        self.offset_left + self.width
+    }
+
+    /// Just past the bottom edge
+    pub fn bottom(&self) -> usize {
+        self.offset_top + self.height
+    }
+
+    pub fn area(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// The number of squares within both claims
+    pub fn overlap_area(&self, other: &Claim) -> usize {
+        let width = self.right().min(other.right());
+        let height = self.bottom().min(other.bottom());
+        width.saturating_sub(self.offset_left.max(other.offset_left))
+            * height.saturating_sub(self.offset_top.max(other.offset_top))
+    }
 
-        pub fn contains(&self, (x, y): Square) -> bool {
-            let hit_x = self.offset_left <= x && x < self.offset_left + self.width;
-            let hit_y = self.offset_top <= y && y < self.offset_top + self.height;
-            hit_x && hit_y
+    pub fn overlaps(&self, other: &Claim) -> bool {
+        self.overlap_area(other) > 0
+    }
+
+    pub fn contains(&self, (x, y): Square) -> bool {
+        let hit_x = self.offset_left <= x && x < self.offset_left + self.width;
+        let hit_y = self.offset_top <= y && y < self.offset_top + self.height;
+        hit_x && hit_y
+    }
+
+    pub fn contended_squares(claims: &[Claim]) -> HashSet<Square> {
+        let squares: HashMultiSet<Square> = claims.iter().flat_map(Claim::squares).collect();
+
+        let mut contended = HashSet::new();
+
+        for square in squares.distinct_elements() {
+            if squares.count_of(square) > 1 {
+                contended.insert(*square);
+            }
         }
 
-        pub fn contended_squares(claims: &[Claim]) -> HashSet<Square> {
-            let squares: HashMultiSet<Square> = claims.iter().flat_map(Claim::squares).collect();
+        contended
+    }
 
-            let mut contended = HashSet::new();
+    /// The number of squares within two or more claims, without visiting each square
+    pub fn contended_area(claims: &[Claim]) -> usize {
+        Claim::area_covered_by_at_least(claims, 2)
+    }
 
-            for square in squares.distinct_elements() {
-                if squares.count_of(square) > 1 {
-                    contended.insert(*square);
+    /// The number of squares covered by exactly `k` claims, indexed by `k` up to the maximum
+    /// depth. Index 0 counts the uncovered squares within the claims' bounding box.
+    pub fn depth_histogram(claims: &[Claim]) -> Vec<usize> {
+        let mut histogram = Vec::new();
+        sweep(claims, |xs, ys, depths| {
+            for (i, &depth) in depths.iter().enumerate() {
+                let depth = depth as usize;
+                if histogram.len() <= depth {
+                    histogram.resize(depth + 1, 0);
                 }
+                histogram[depth] += xs.len() * (ys[i + 1] - ys[i]);
             }
+        });
+        histogram
+    }
 
-            contended
-        }
+    pub fn area_covered_by_at_least(claims: &[Claim], k: usize) -> usize {
+        Claim::depth_histogram(claims).iter().skip(k).sum()
+    }
+
+    /// Disjoint rectangles which together cover exactly the squares within at least `k` claims
+    pub fn covered_by_at_least(claims: &[Claim], k: usize) -> Vec<Rect> {
+        let mut rects = Vec::new();
+        sweep(claims, |xs, ys, depths| {
+            // merge vertically adjacent segments which are all deep enough
+            let mut run_start = None;
+            for (i, &depth) in depths.iter().enumerate() {
+                match (depth as usize >= k, run_start) {
+                    (true, None) => run_start = Some(ys[i]),
+                    (false, Some(top)) => {
+                        rects.push(Rect::new(xs.start, top, xs.len(), ys[i] - top));
+                        run_start = None;
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(top) = run_start {
+                rects.push(Rect::new(xs.start, top, xs.len(), ys[depths.len()] - top));
+            }
+        });
+        rects
+    }
 
-        pub fn find_uncontended_claims(claims: &[Claim]) -> Vec<Claim> {
-            let contended = Claim::contended_squares(claims);
+    /// The most claims covering any one square, and a square they all cover
+    pub fn max_depth(claims: &[Claim]) -> Option<(usize, Square)> {
+        let mut deepest: Option<(usize, Square)> = None;
+        sweep(claims, |xs, ys, depths| {
+            for (i, &depth) in depths.iter().enumerate() {
+                let depth = depth as usize;
+                if depth > 0 && deepest.is_none_or(|(max, _)| depth > max) {
+                    deepest = Some((depth, (xs.start, ys[i])));
+                }
+            }
+        });
+        deepest
+    }
 
-            let is_contended =
-                |claim: &Claim| -> bool { contended.iter().any(|&square| claim.contains(square)) };
+    /// Claims which overlap no other claim, in their original order
+    pub fn find_uncontended_claims(claims: &[Claim]) -> Vec<Claim> {
+        let index = ClaimIndex::new(claims);
+        claims
+            .iter()
+            .filter(|claim| index.overlapping(claim).len() <= 1)
+            .copied()
+            .collect()
+    }
+}
 
-            let result: Vec<Claim> = claims
-                .iter()
-                .filter(|claim| !is_contended(claim))
-                .map(|&claim| claim)
-                .collect();
+/// A region of fabric, with no claim id
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub struct Rect {
+    pub left: usize,
+    pub top: usize,
+    pub width: usize,
+    pub height: usize,
+}
 
-            result
+impl Rect {
+    pub fn new(left: usize, top: usize, width: usize, height: usize) -> Rect {
+        Rect {
+            left,
+            top,
+            width,
+            height,
         }
     }
 
-    impl FromStr for Claim {
-        type Err = ParseIntError;
+    pub fn area(&self) -> usize {
+        self.width * self.height
+    }
 
-        fn from_str(line: &str) -> Result<Self, Self::Err> {
-            lazy_static! {
-                static ref RE: Regex = Regex::new(r"^#(?P<id>\d+) @ (?P<offset_left>\d+),(?P<offset_top>\d+): (?P<width>\d+)x(?P<height>\d+)$").unwrap();
+    pub fn squares(&self) -> impl Iterator<Item = Square> {
+        let xs = self.left..(self.left + self.width);
+        let ys = self.top..(self.top + self.height);
+        xs.cartesian_product(ys)
+    }
+}
+
+/// Sweeps left to right across the fabric, calling `visit` for each vertical slab between
+/// consecutive claim edges with the slab's x range, the y coordinates cutting it into
+/// segments (segment `i` runs from `ys[i]` to `ys[i + 1]`) and how many claims cover each
+/// segment.
+///
+/// Only claim edges are visited, so the cost depends on the number of claims rather than on
+/// their size or position: O(n²) for n claims.
+fn sweep(claims: &[Claim], mut visit: impl FnMut(Range<usize>, &[usize], &[u32])) {
+    let claims: Vec<&Claim> = claims.iter().filter(|claim| claim.area() > 0).collect();
+
+    let ys: Vec<usize> = claims
+        .iter()
+        .flat_map(|claim| vec![claim.offset_top, claim.bottom()])
+        .sorted()
+        .dedup()
+        .collect();
+    let segment = |y: usize| ys.binary_search(&y).unwrap();
+
+    // (x, +1 or -1, first segment, last segment exclusive)
+    let mut edges: Vec<(usize, i32, usize, usize)> = Vec::with_capacity(claims.len() * 2);
+    for claim in &claims {
+        let (top, bottom) = (segment(claim.offset_top), segment(claim.bottom()));
+        edges.push((claim.offset_left, 1, top, bottom));
+        edges.push((claim.right(), -1, top, bottom));
+    }
+    edges.sort_unstable();
+
+    let mut depths = vec![0u32; ys.len().saturating_sub(1)];
+    let mut previous_x = None;
+    for (x, edges) in &edges.iter().chunk_by(|edge| edge.0) {
+        if let Some(previous_x) = previous_x {
+            visit(previous_x..x, &ys, &depths);
+        }
+        for &(_, change, top, bottom) in edges {
+            for depth in &mut depths[top..bottom] {
+                *depth = (*depth as i32 + change) as u32;
             }
-            let captures = RE.captures(line).unwrap();
-
-            Ok(Claim {
-                id: captures.name("id").unwrap().as_str().parse()?,
-                offset_left: captures.name("offset_left").unwrap().as_str().parse()?,
-                offset_top: captures.name("offset_top").unwrap().as_str().parse()?,
-                width: captures.name("width").unwrap().as_str().parse()?,
-                height: captures.name("height").unwrap().as_str().parse()?,
-            })
         }
+        previous_x = Some(x);
+    }
+}
+
+impl Display for Claim {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "#{} @ {},{}: {}x{}",
+            self.id, self.offset_left, self.offset_top, self.width, self.height
+        )
+    }
+}
+
+impl FromStr for Claim {
+    type Err = ParseError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"^#(?P<id>\d+) @ (?P<offset_left>\d+),(?P<offset_top>\d+): (?P<width>\d+)x(?P<height>\d+)$").unwrap();
+        }
+        let captures = RE
+            .captures(line)
+            .ok_or_else(|| ParseError::new(line, 0, "a claim like '#123 @ 3,2: 5x4'"))?;
+        let number = |name| parse_capture(line, &captures, name, "a number that fits in usize");
+
+        Ok(Claim {
+            id: number("id")?,
+            offset_left: number("offset_left")?,
+            offset_top: number("offset_top")?,
+            width: number("width")?,
+            height: number("height")?,
+        })
     }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generate;
+    use crate::generate::Rng;
+    use crate::input;
 
     #[test]
     fn parse_single_line() {
@@ -107,6 +310,18 @@ mod test {
         )
     }
 
+    #[test]
+    fn malformed_claims_are_errors() {
+        let error = Claim::from_str("#123 @ 3,2 5x4").unwrap_err();
+        assert_eq!(error.column, 1);
+
+        let error = Claim::from_str("#123 @ 3,99999999999999999999999: 5x4").unwrap_err();
+        assert_eq!(error.column, 10);
+
+        let error = parse("#1 @ 1,1: 1x1\n#2 @ 1,1 1x1").unwrap_err();
+        assert_eq!(error.line, 2);
+    }
+
     #[test]
     fn parses_entire_input() {
         assert_eq!(input().len(), 1381);
@@ -129,6 +344,83 @@ mod test {
         assert_eq!(Claim::contended_squares(&[c1, c2, c3]).len(), 1);
     }
 
+    #[test]
+    fn contended_area_matches_contended_squares() {
+        assert_eq!(Claim::contended_area(&[]), 0);
+        for seed in 0..20 {
+            let claims = parse(&generate::day3(&mut Rng::seeded(seed), 100, 200)).unwrap();
+            assert_eq!(
+                Claim::contended_area(&claims),
+                Claim::contended_squares(&claims).len(),
+                "seed {}",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn contended_area_handles_huge_fabric() {
+        let claims = parse(
+            "#1 @ 1000000,2000000: 3000000x4000000\n\
+             #2 @ 2000000,1000000: 5000000x2000000\n\
+             #3 @ 0,0: 1x1\n\
+             #4 @ 3999999,2999999: 0x10",
+        )
+        .unwrap();
+        // 2000000 wide (x from 2000000 to 4000000), 1000000 high (y from 2000000 to 3000000)
+        assert_eq!(Claim::contended_area(&claims), 2_000_000_000_000);
+    }
+
+    #[test]
+    fn depth_queries_match_counting_squares() {
+        for seed in 0..20 {
+            let claims = parse(&generate::day3(&mut Rng::seeded(seed), 60, 100)).unwrap();
+            let squares: HashMultiSet<Square> = claims.iter().flat_map(Claim::squares).collect();
+            let depth = |square| squares.count_of(square);
+
+            let histogram = Claim::depth_histogram(&claims);
+            for (k, &area) in histogram.iter().enumerate().skip(1) {
+                let expected = squares
+                    .distinct_elements()
+                    .filter(|s| depth(s) == k)
+                    .count();
+                assert_eq!(area, expected, "seed {}, depth {}", seed, k);
+            }
+
+            let max_depth = histogram.len() - 1;
+            let (deepest, witness) = Claim::max_depth(&claims).unwrap();
+            assert_eq!(deepest, max_depth);
+            assert_eq!(depth(&witness), max_depth);
+
+            for k in 1..=max_depth + 1 {
+                let covered: HashSet<Square> = Claim::covered_by_at_least(&claims, k)
+                    .iter()
+                    .flat_map(Rect::squares)
+                    .collect();
+                let expected: HashSet<Square> = squares
+                    .distinct_elements()
+                    .filter(|s| depth(s) >= k)
+                    .copied()
+                    .collect();
+                assert_eq!(covered, expected, "seed {}, depth {}", seed, k);
+                assert_eq!(Claim::area_covered_by_at_least(&claims, k), expected.len());
+            }
+        }
+    }
+
+    #[test]
+    fn example_depths() {
+        let claims = parse("#1 @ 1,3: 4x4\n#2 @ 3,1: 4x4\n#3 @ 5,5: 2x2").unwrap();
+        // the bounding box is 6x6, with 28 squares claimed once, 4 twice and 4 not at all
+        assert_eq!(Claim::depth_histogram(&claims), vec![4, 28, 4]);
+        assert_eq!(Claim::max_depth(&claims), Some((2, (3, 3))));
+        assert_eq!(
+            Claim::covered_by_at_least(&claims, 2),
+            vec![Rect::new(3, 3, 2, 2)]
+        );
+        assert_eq!(Claim::max_depth(&[]), None);
+    }
+
     #[test]
     fn contains_works() {
         assert!(Claim::from_str("#123 @ 0,0: 1x1").unwrap().contains((0, 0)));
@@ -138,11 +430,26 @@ mod test {
         assert!(!Claim::from_str("#123 @ 0,0: 2x2").unwrap().contains((3, 3)));
     }
 
+    #[test]
+    fn display_round_trips() {
+        for line in input::load_day(3).unwrap().lines() {
+            assert_eq!(Claim::from_str(line).unwrap().to_string(), line);
+        }
+
+        let rng = &mut Rng::seeded(23);
+        for _ in 0..100 {
+            let claim = Claim {
+                id: rng.below(usize::MAX),
+                offset_left: rng.below(usize::MAX),
+                offset_top: rng.below(1000),
+                width: rng.below(usize::MAX),
+                height: rng.below(1000),
+            };
+            assert_eq!(Claim::from_str(&claim.to_string()), Ok(claim));
+        }
+    }
+
     fn input() -> Vec<Claim> {
-        fs::read_to_string("day3.txt")
-            .unwrap()
-            .lines()
-            .map(|l| l.parse().unwrap())
-            .collect()
+        parse(&input::load_day(3).unwrap()).unwrap()
     }
 }
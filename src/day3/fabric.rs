@@ -0,0 +1,204 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::iter::FromIterator;
+
+use super::Claim;
+
+/// Claims which come and go, with overlap statistics kept up to date as they do.
+///
+/// Adding or removing a claim costs a scan over the other claims plus a sweep over just the
+/// ones it conflicts with, rather than recomputing everything.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Fabric {
+    claims: BTreeMap<usize, Claim>,
+    /// claim id to the id of every claim it overlaps, with the overlapping area
+    conflicts: BTreeMap<usize, BTreeMap<usize, usize>>,
+    uncontended: BTreeSet<usize>,
+    contended_area: usize,
+}
+
+impl Fabric {
+    pub fn new() -> Fabric {
+        Fabric::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.claims.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.claims.is_empty()
+    }
+
+    pub fn claims(&self) -> impl Iterator<Item = &Claim> {
+        self.claims.values()
+    }
+
+    /// Adds a claim, unless one with the same id is already present
+    pub fn add(&mut self, claim: Claim) -> bool {
+        if self.claims.contains_key(&claim.id) {
+            return false;
+        }
+
+        // squares which only one other claim covered are now contended
+        self.contended_area += self.covered_once_by_others(&claim);
+
+        let conflicts: BTreeMap<usize, usize> = self
+            .claims
+            .values()
+            .filter(|other| claim.overlaps(other))
+            .map(|other| (other.id, claim.overlap_area(other)))
+            .collect();
+        for (&other, &area) in &conflicts {
+            self.conflicts
+                .get_mut(&other)
+                .unwrap()
+                .insert(claim.id, area);
+            self.uncontended.remove(&other);
+        }
+        if conflicts.is_empty() {
+            self.uncontended.insert(claim.id);
+        }
+
+        self.conflicts.insert(claim.id, conflicts);
+        self.claims.insert(claim.id, claim);
+        true
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<Claim> {
+        let claim = self.claims.remove(&id)?;
+        let conflicts = self.conflicts.remove(&id).unwrap();
+        self.uncontended.remove(&id);
+
+        for other in conflicts.keys() {
+            let others_conflicts = self.conflicts.get_mut(other).unwrap();
+            others_conflicts.remove(&id);
+            if others_conflicts.is_empty() {
+                self.uncontended.insert(*other);
+            }
+        }
+
+        // squares which only one other claim covers are no longer contended
+        self.contended_area -= self.covered_once_by_others(&claim);
+        Some(claim)
+    }
+
+    /// The number of squares within two or more claims
+    pub fn contended_area(&self) -> usize {
+        self.contended_area
+    }
+
+    /// Claims which overlap no other claim, by id
+    pub fn uncontended(&self) -> impl Iterator<Item = &Claim> {
+        self.uncontended.iter().map(move |id| &self.claims[id])
+    }
+
+    /// Every claim overlapping the claim with this id, with the overlapping area
+    pub fn conflicts(&self, id: usize) -> Option<&BTreeMap<usize, usize>> {
+        self.conflicts.get(&id)
+    }
+
+    /// Squares of `claim` covered by exactly one of the claims already on the fabric, other
+    /// than `claim` itself
+    fn covered_once_by_others(&self, claim: &Claim) -> usize {
+        let clipped: Vec<Claim> = self
+            .claims
+            .values()
+            .filter(|other| other.id != claim.id && claim.overlaps(other))
+            .map(|other| {
+                let left = claim.offset_left.max(other.offset_left);
+                let top = claim.offset_top.max(other.offset_top);
+                Claim {
+                    id: other.id,
+                    offset_left: left,
+                    offset_top: top,
+                    width: claim.right().min(other.right()) - left,
+                    height: claim.bottom().min(other.bottom()) - top,
+                }
+            })
+            .collect();
+        Claim::depth_histogram(&clipped)
+            .get(1)
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+impl FromIterator<Claim> for Fabric {
+    fn from_iter<I: IntoIterator<Item = Claim>>(claims: I) -> Self {
+        let mut fabric = Fabric::new();
+        for claim in claims {
+            fabric.add(claim);
+        }
+        fabric
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{parse, ClaimIndex};
+    use super::*;
+    use crate::generate;
+    use crate::generate::Rng;
+
+    /// Recomputes everything `fabric` keeps track of from scratch
+    fn assert_consistent(fabric: &Fabric) {
+        let claims: Vec<Claim> = fabric.claims().copied().collect();
+        assert_eq!(fabric.contended_area(), Claim::contended_area(&claims));
+
+        let uncontended: Vec<Claim> = fabric.uncontended().copied().collect();
+        assert_eq!(uncontended, Claim::find_uncontended_claims(&claims));
+
+        for (id, overlaps) in ClaimIndex::new(&claims).overlap_graph() {
+            let conflicts: Vec<(usize, usize)> = fabric
+                .conflicts(id)
+                .unwrap()
+                .iter()
+                .map(|(&id, &area)| (id, area))
+                .collect();
+            assert_eq!(conflicts, overlaps);
+        }
+    }
+
+    #[test]
+    fn stays_consistent_through_adds_and_removes() {
+        let rng = &mut Rng::seeded(21);
+        let claims = parse(&generate::day3(rng, 80, 120)).unwrap();
+        let mut fabric = Fabric::new();
+
+        for claim in &claims {
+            assert!(fabric.add(*claim));
+            assert_consistent(&fabric);
+        }
+        assert!(!fabric.add(claims[0]));
+
+        let mut ids: Vec<usize> = claims.iter().map(|claim| claim.id).collect();
+        rng.shuffle(&mut ids);
+        for id in ids {
+            assert_eq!(fabric.remove(id).map(|claim| claim.id), Some(id));
+            assert_consistent(&fabric);
+        }
+        assert!(fabric.is_empty());
+        assert_eq!(fabric.remove(1), None);
+    }
+
+    #[test]
+    fn example() {
+        let mut fabric: Fabric = parse("#1 @ 1,3: 4x4\n#2 @ 3,1: 4x4\n#3 @ 5,5: 2x2")
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(fabric.contended_area(), 4);
+        assert_eq!(
+            fabric
+                .uncontended()
+                .map(|claim| claim.id)
+                .collect::<Vec<_>>(),
+            vec![3]
+        );
+
+        fabric.remove(2);
+        assert_eq!(fabric.contended_area(), 0);
+        assert_eq!(fabric.uncontended().count(), 2);
+        assert!(fabric.conflicts(1).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+
+use super::Claim;
+
+/// Entries per node of the tree
+const NODE_CAPACITY: usize = 8;
+
+/// A static R-tree over claims, bulk-loaded with Sort-Tile-Recursive packing so that nearby
+/// claims share nodes
+#[derive(Debug, Clone)]
+pub struct ClaimIndex {
+    claims: Vec<Claim>,
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    bounds: Bounds,
+    /// indices into `claims` for leaves, otherwise into `nodes`
+    entries: Vec<usize>,
+    leaf: bool,
+}
+
+/// Edges of a rectangle, with `right` and `bottom` just outside it
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bounds {
+    left: usize,
+    top: usize,
+    right: usize,
+    bottom: usize,
+}
+
+impl Bounds {
+    fn of(claim: &Claim) -> Bounds {
+        Bounds {
+            left: claim.offset_left,
+            top: claim.offset_top,
+            right: claim.right(),
+            bottom: claim.bottom(),
+        }
+    }
+
+    fn union(self, other: Bounds) -> Bounds {
+        Bounds {
+            left: self.left.min(other.left),
+            top: self.top.min(other.top),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+        }
+    }
+
+    fn overlaps(&self, other: &Bounds) -> bool {
+        self.left < other.right
+            && other.left < self.right
+            && self.top < other.bottom
+            && other.top < self.bottom
+    }
+
+    fn centre(&self) -> (usize, usize) {
+        (
+            self.left + (self.right - self.left) / 2,
+            self.top + (self.bottom - self.top) / 2,
+        )
+    }
+}
+
+impl ClaimIndex {
+    pub fn new(claims: &[Claim]) -> ClaimIndex {
+        let mut index = ClaimIndex {
+            claims: claims.to_vec(),
+            nodes: Vec::new(),
+        };
+
+        let mut level: Vec<(Bounds, usize)> = index
+            .claims
+            .iter()
+            .enumerate()
+            .map(|(i, claim)| (Bounds::of(claim), i))
+            .collect();
+        let mut leaf = true;
+        while level.len() > 1 || leaf {
+            level = index.pack(level, leaf);
+            leaf = false;
+        }
+        index
+    }
+
+    /// Groups one level's entries into nodes, returning the next level up
+    fn pack(&mut self, mut entries: Vec<(Bounds, usize)>, leaf: bool) -> Vec<(Bounds, usize)> {
+        let nodes = entries.len().div_ceil(NODE_CAPACITY).max(1);
+        let slices = (nodes as f64).sqrt().ceil() as usize;
+        let slice_len = (slices * NODE_CAPACITY).max(1);
+
+        entries.sort_by_key(|(bounds, _)| bounds.centre().0);
+        let mut parents = Vec::new();
+        for slice in entries.chunks_mut(slice_len) {
+            slice.sort_by_key(|(bounds, _)| bounds.centre().1);
+            for group in slice.chunks(NODE_CAPACITY) {
+                let bounds = group
+                    .iter()
+                    .map(|&(bounds, _)| bounds)
+                    .reduce(Bounds::union)
+                    .unwrap();
+                parents.push((bounds, self.nodes.len()));
+                self.nodes.push(Node {
+                    bounds,
+                    entries: group.iter().map(|&(_, entry)| entry).collect(),
+                    leaf,
+                });
+            }
+        }
+        parents
+    }
+
+    pub fn claims(&self) -> &[Claim] {
+        &self.claims
+    }
+
+    /// Every claim sharing at least one square with `area`, whose id is ignored
+    pub fn overlapping(&self, area: &Claim) -> Vec<&Claim> {
+        let bounds = Bounds::of(area);
+        let mut found: Vec<&Claim> = Vec::new();
+        // the root is the last node packed
+        let mut stack: Vec<usize> = self.nodes.len().checked_sub(1).into_iter().collect();
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bounds.overlaps(&bounds) {
+                continue;
+            }
+            if node.leaf {
+                found.extend(
+                    node.entries
+                        .iter()
+                        .map(|&i| &self.claims[i])
+                        .filter(|claim| claim.overlaps(area)),
+                );
+            } else {
+                stack.extend(&node.entries);
+            }
+        }
+
+        found.sort_by_key(|claim| claim.id);
+        found
+    }
+
+    /// Claim id to the ids of every other claim it overlaps, with the area of each overlap.
+    /// Claims overlapping nothing map to an empty list.
+    pub fn overlap_graph(&self) -> BTreeMap<usize, Vec<(usize, usize)>> {
+        self.claims
+            .iter()
+            .map(|claim| {
+                let overlaps = self
+                    .overlapping(claim)
+                    .into_iter()
+                    .filter(|other| !std::ptr::eq(*other, claim))
+                    .map(|other| (other.id, claim.overlap_area(other)))
+                    .collect();
+                (claim.id, overlaps)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::parse;
+    use super::*;
+    use crate::generate;
+    use crate::generate::Rng;
+
+    #[test]
+    fn overlap_graph_matches_all_pairs() {
+        for seed in 0..10 {
+            let claims = parse(&generate::day3(&mut Rng::seeded(seed), 150, 300)).unwrap();
+            let index = ClaimIndex::new(&claims);
+
+            for claim in &claims {
+                let expected: Vec<(usize, usize)> = claims
+                    .iter()
+                    .filter(|other| other.id != claim.id && claim.overlaps(other))
+                    .map(|other| (other.id, claim.overlap_area(other)))
+                    .collect();
+                assert_eq!(index.overlap_graph()[&claim.id], expected, "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn queries_rectangles() {
+        let claims = parse("#1 @ 1,3: 4x4\n#2 @ 3,1: 4x4\n#3 @ 5,5: 2x2\n#4 @ 2,2: 0x5").unwrap();
+        let index = ClaimIndex::new(&claims);
+
+        let ids = |area: &str| -> Vec<usize> {
+            let area = area.parse().unwrap();
+            index
+                .overlapping(&area)
+                .iter()
+                .map(|claim| claim.id)
+                .collect()
+        };
+        assert_eq!(ids("#0 @ 0,0: 10x10"), vec![1, 2, 3]);
+        assert_eq!(ids("#0 @ 4,4: 1x1"), vec![1, 2]);
+        assert_eq!(ids("#0 @ 5,3: 1x1"), vec![2]);
+        assert_eq!(ids("#0 @ 7,7: 3x3"), Vec::<usize>::new());
+
+        assert!(ClaimIndex::new(&[]).overlap_graph().is_empty());
+    }
+}
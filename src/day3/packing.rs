@@ -0,0 +1,279 @@
+use std::cmp::Reverse;
+
+use itertools::Itertools;
+
+use super::{Claim, Rect};
+
+/// Finds room for new claims on a bounded fabric, around the claims already on it, so that
+/// nothing new overlaps.
+///
+/// Uses the maximal rectangles heuristic: the free space is kept as every largest empty
+/// rectangle (these may overlap each other), and each claim goes in the top left corner of
+/// whichever one it fits most snugly. Because every maximal rectangle is kept, a claim is only
+/// turned away when there's no room for it anywhere.
+#[derive(Debug, Clone)]
+pub struct Planner {
+    fabric: Rect,
+    claims: Vec<Claim>,
+    free: Vec<Rect>,
+    next_id: usize,
+}
+
+/// Where requested claims went
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    /// by id, which follows request order
+    pub placed: Vec<Claim>,
+    /// indices of the requests with no room
+    pub unplaced: Vec<usize>,
+}
+
+impl Planner {
+    /// New claims are numbered after the highest existing id
+    pub fn new(fabric: Rect, existing: &[Claim]) -> Planner {
+        let mut planner = Planner {
+            fabric,
+            claims: Vec::new(),
+            free: if fabric.area() > 0 {
+                vec![fabric]
+            } else {
+                vec![]
+            },
+            next_id: existing.iter().map(|claim| claim.id + 1).max().unwrap_or(1),
+        };
+        for &claim in existing {
+            planner.occupy(claim);
+        }
+        planner
+    }
+
+    /// Existing claims, then placed ones
+    pub fn claims(&self) -> &[Claim] {
+        &self.claims
+    }
+
+    /// Whether `claim` is non-empty, lies within the fabric and overlaps nothing on it
+    pub fn fits(&self, claim: &Claim) -> bool {
+        claim.area() > 0
+            && claim.offset_left >= self.fabric.left
+            && claim.offset_top >= self.fabric.top
+            && claim.right() <= self.fabric.left + self.fabric.width
+            && claim.bottom() <= self.fabric.top + self.fabric.height
+            && !self.claims.iter().any(|other| other.overlaps(claim))
+    }
+
+    /// Places a `width` by `height` claim with the next id, if there's room
+    pub fn place(&mut self, width: usize, height: usize) -> Option<Claim> {
+        let claim = self.place_as(self.next_id, width, height)?;
+        self.next_id += 1;
+        Some(claim)
+    }
+
+    /// Places as many `(width, height)` requests as possible, biggest first since those are
+    /// hardest to fit in later
+    pub fn plan(&mut self, requests: &[(usize, usize)]) -> Plan {
+        let first_id = self.next_id;
+        self.next_id += requests.len();
+
+        let mut placed = Vec::new();
+        let mut unplaced = Vec::new();
+        let biggest_first = (0..requests.len()).sorted_by_key(|&i| {
+            let (width, height) = requests[i];
+            (Reverse(width.max(height)), Reverse(width * height), i)
+        });
+        for i in biggest_first {
+            let (width, height) = requests[i];
+            match self.place_as(first_id + i, width, height) {
+                Some(claim) => placed.push(claim),
+                None => unplaced.push(i),
+            }
+        }
+
+        placed.sort_by_key(|claim| claim.id);
+        unplaced.sort();
+        Plan { placed, unplaced }
+    }
+
+    fn place_as(&mut self, id: usize, width: usize, height: usize) -> Option<Claim> {
+        // best short side fit: least leftover on the tighter side, then on the other
+        let claim = self
+            .free
+            .iter()
+            .filter(|free| free.width >= width && free.height >= height)
+            .sorted_by_key(|free| {
+                let leftover = (free.width - width, free.height - height);
+                (
+                    leftover.0.min(leftover.1),
+                    leftover.0.max(leftover.1),
+                    free.top,
+                    free.left,
+                )
+            })
+            .map(|free| Claim {
+                id,
+                offset_left: free.left,
+                offset_top: free.top,
+                width,
+                height,
+            })
+            .find(|claim| self.fits(claim))?;
+        self.occupy(claim);
+        Some(claim)
+    }
+
+    /// Carves `claim` out of every free rectangle it overlaps
+    fn occupy(&mut self, claim: Claim) {
+        let (mut free, split): (Vec<Rect>, Vec<Rect>) = self
+            .free
+            .drain(..)
+            .partition(|&rect| claim.overlap_area(&as_claim(rect)) == 0);
+
+        let mut pieces = Vec::new();
+        for rect in split {
+            let (right, bottom) = (rect.left + rect.width, rect.top + rect.height);
+            if claim.offset_left > rect.left {
+                pieces.push(Rect::new(
+                    rect.left,
+                    rect.top,
+                    claim.offset_left - rect.left,
+                    rect.height,
+                ));
+            }
+            if claim.right() < right {
+                pieces.push(Rect::new(
+                    claim.right(),
+                    rect.top,
+                    right - claim.right(),
+                    rect.height,
+                ));
+            }
+            if claim.offset_top > rect.top {
+                pieces.push(Rect::new(
+                    rect.left,
+                    rect.top,
+                    rect.width,
+                    claim.offset_top - rect.top,
+                ));
+            }
+            if claim.bottom() < bottom {
+                pieces.push(Rect::new(
+                    rect.left,
+                    claim.bottom(),
+                    rect.width,
+                    bottom - claim.bottom(),
+                ));
+            }
+        }
+
+        // Only maximal rectangles are worth keeping, and only one copy of each. Untouched
+        // rectangles were already maximal, and can't lie inside a piece of a bigger one.
+        for (i, piece) in pieces.iter().enumerate() {
+            let enclosed = |(j, other): (usize, &Rect)| {
+                j != i && encloses(other, piece) && (other != piece || j < i)
+            };
+            if !free.iter().any(|other| encloses(other, piece))
+                && !pieces.iter().enumerate().any(enclosed)
+            {
+                free.push(*piece);
+            }
+        }
+        self.free = free;
+        self.claims.push(claim);
+    }
+}
+
+fn encloses(outer: &Rect, inner: &Rect) -> bool {
+    as_claim(*outer).overlap_area(&as_claim(*inner)) == inner.area()
+}
+
+fn as_claim(rect: Rect) -> Claim {
+    Claim {
+        id: 0,
+        offset_left: rect.left,
+        offset_top: rect.top,
+        width: rect.width,
+        height: rect.height,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::parse;
+    use super::*;
+    use crate::generate;
+    use crate::generate::Rng;
+
+    /// Nothing placed overlaps anything else or leaves the fabric, and no unplaced request
+    /// would fit anywhere in what's left
+    fn check(fabric: Rect, existing: &[Claim], requests: &[(usize, usize)], plan: &Plan) {
+        let mut planner = Planner::new(fabric, existing);
+        for claim in &plan.placed {
+            assert!(planner.fits(claim), "{} doesn't fit", claim);
+            assert_eq!(
+                (claim.width, claim.height),
+                requests[claim.id - planner.next_id]
+            );
+            planner.claims.push(*claim);
+        }
+        assert_eq!(plan.placed.len() + plan.unplaced.len(), requests.len());
+
+        for &i in &plan.unplaced {
+            let (width, height) = requests[i];
+            for (left, top) in fabric.squares() {
+                let claim = Claim {
+                    id: 0,
+                    offset_left: left,
+                    offset_top: top,
+                    width,
+                    height,
+                };
+                assert!(!planner.fits(&claim), "request {} fits at {}", i, claim);
+            }
+        }
+    }
+
+    #[test]
+    fn packs_around_existing_claims() {
+        let existing = parse("#1 @ 1,3: 4x4\n#2 @ 3,1: 4x4\n#3 @ 5,5: 2x2").unwrap();
+        let fabric = Rect::new(0, 0, 8, 8);
+        let requests = [(1, 8), (2, 1), (8, 1), (0, 1), (3, 3)];
+        let plan = Planner::new(fabric, &existing).plan(&requests);
+
+        // the column takes the first square of the only free row, and a corner of the only
+        // 3x3 gap
+        assert_eq!(plan.unplaced, vec![2, 3, 4]);
+        assert_eq!(
+            plan.placed.iter().map(|claim| claim.id).collect_vec(),
+            vec![4, 5]
+        );
+        assert_eq!(plan.placed[0].to_string(), "#4 @ 0,0: 1x8");
+        check(fabric, &existing, &requests, &plan);
+    }
+
+    #[test]
+    fn fills_an_empty_fabric_exactly() {
+        let fabric = Rect::new(10, 20, 4, 4);
+        let mut planner = Planner::new(fabric, &[]);
+        let requests = [(2, 2), (1, 4), (3, 2), (1, 2)];
+        let plan = planner.plan(&requests);
+        assert!(plan.unplaced.is_empty());
+        check(fabric, &[], &requests, &plan);
+        assert_eq!(planner.place(1, 1), None);
+        assert_eq!(Claim::contended_area(planner.claims()), 0);
+    }
+
+    #[test]
+    fn plans_match_brute_force() {
+        for seed in 0..10 {
+            let rng = &mut Rng::seeded(seed);
+            let existing = parse(&generate::day3(rng, 6, 30)).unwrap();
+            let requests: Vec<(usize, usize)> = (0..15)
+                .map(|_| (1 + rng.below(10), 1 + rng.below(10)))
+                .collect();
+            let fabric = Rect::new(0, 0, 30, 30);
+
+            let plan = Planner::new(fabric, &existing).plan(&requests);
+            check(fabric, &existing, &requests, &plan);
+        }
+    }
+}
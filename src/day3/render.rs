@@ -0,0 +1,381 @@
+use std::collections::HashSet;
+
+use super::{Claim, Rect};
+
+/// What a square's colour (or character) says about it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Colouring {
+    /// how many claims cover it
+    Depth,
+    /// which claim covers it, if only one does
+    Claim,
+}
+
+/// Draws claims on the fabric as ASCII art or as an image
+#[derive(Debug, Clone)]
+pub struct Renderer<'a> {
+    claims: &'a [Claim],
+    colouring: Colouring,
+    crop: Option<Rect>,
+    highlight_uncontended: bool,
+}
+
+/// One square of the rendered area
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Cell {
+    depth: usize,
+    /// the id of the last claim drawn here
+    owner: usize,
+    uncontended: bool,
+}
+
+type Rgb = [u8; 3];
+
+const BACKGROUND: Rgb = [24, 24, 24];
+const CONTESTED: Rgb = [255, 255, 255];
+const HIGHLIGHT: Rgb = [0, 255, 64];
+/// by depth, with anything deeper using the last colour
+const HEAT: [Rgb; 5] = [
+    BACKGROUND,
+    [40, 90, 200],
+    [240, 220, 40],
+    [250, 140, 20],
+    [220, 30, 30],
+];
+
+impl<'a> Renderer<'a> {
+    /// By default, colours by depth over the whole fabric from `0,0` without highlighting
+    pub fn new(claims: &'a [Claim]) -> Renderer<'a> {
+        Renderer {
+            claims,
+            colouring: Colouring::Depth,
+            crop: None,
+            highlight_uncontended: false,
+        }
+    }
+
+    pub fn colouring(mut self, colouring: Colouring) -> Self {
+        self.colouring = colouring;
+        self
+    }
+
+    /// Only draws this part of the fabric
+    pub fn crop(mut self, area: Rect) -> Self {
+        self.crop = Some(area);
+        self
+    }
+
+    /// Picks out claims which overlap no others, as found by `find_uncontended_claims`
+    pub fn highlight_uncontended(mut self, highlight: bool) -> Self {
+        self.highlight_uncontended = highlight;
+        self
+    }
+
+    /// The part of the fabric drawn: the crop, or from `0,0` to the furthest edges of the claims
+    pub fn area(&self) -> Rect {
+        self.crop.unwrap_or_else(|| {
+            let right = self.claims.iter().map(Claim::right).max().unwrap_or(0);
+            let bottom = self.claims.iter().map(Claim::bottom).max().unwrap_or(0);
+            Rect::new(0, 0, right, bottom)
+        })
+    }
+
+    /// Rows of cells within `area()`
+    fn cells(&self) -> Vec<Vec<Cell>> {
+        let area = self.area();
+        let mut cells = vec![vec![Cell::default(); area.width]; area.height];
+        let uncontended: HashSet<usize> = if self.highlight_uncontended {
+            Claim::find_uncontended_claims(self.claims)
+                .iter()
+                .map(|claim| claim.id)
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        for claim in self.claims {
+            let left = claim.offset_left.max(area.left);
+            let top = claim.offset_top.max(area.top);
+            let right = claim.right().min(area.left + area.width);
+            let bottom = claim.bottom().min(area.top + area.height);
+            if left >= right || top >= bottom {
+                continue;
+            }
+            for row in &mut cells[top - area.top..bottom - area.top] {
+                for cell in &mut row[left - area.left..right - area.left] {
+                    cell.depth += 1;
+                    cell.owner = claim.id;
+                    cell.uncontended = uncontended.contains(&claim.id);
+                }
+            }
+        }
+        cells
+    }
+
+    /// One character per square.
+    ///
+    /// By depth: `.` for unclaimed, the depth from 1 to 9, then `+`, with `@` for
+    /// highlighted claims. By claim: a letter for the owning claim's id, `#` where claims
+    /// overlap, with highlighted claims in upper case.
+    pub fn ascii(&self) -> String {
+        let mut ascii = String::new();
+        for row in self.cells() {
+            for cell in row {
+                ascii.push(match (self.colouring, cell.depth) {
+                    (_, 0) => '.',
+                    (Colouring::Depth, _) if cell.uncontended => '@',
+                    (Colouring::Depth, depth @ 1..=9) => (b'0' + depth as u8) as char,
+                    (Colouring::Depth, _) => '+',
+                    (Colouring::Claim, 1) => {
+                        let letter = (b'a' + (cell.owner % 26) as u8) as char;
+                        if cell.uncontended {
+                            letter.to_ascii_uppercase()
+                        } else {
+                            letter
+                        }
+                    }
+                    (Colouring::Claim, _) => '#',
+                });
+            }
+            ascii.push('\n');
+        }
+        ascii
+    }
+
+    fn pixels(&self) -> (Rect, Vec<Rgb>) {
+        let pixels = self
+            .cells()
+            .into_iter()
+            .flatten()
+            .map(|cell| match (self.colouring, cell.depth) {
+                (_, 0) => BACKGROUND,
+                (_, _) if cell.uncontended => HIGHLIGHT,
+                (Colouring::Depth, depth) => HEAT[depth.min(HEAT.len() - 1)],
+                (Colouring::Claim, 1) => claim_colour(cell.owner),
+                (Colouring::Claim, _) => CONTESTED,
+            })
+            .collect();
+        (self.area(), pixels)
+    }
+
+    /// A binary (P6) PPM image, one pixel per square
+    pub fn ppm(&self) -> Vec<u8> {
+        let (area, pixels) = self.pixels();
+        let mut ppm = format!("P6\n{} {}\n255\n", area.width, area.height).into_bytes();
+        ppm.extend(pixels.iter().flatten());
+        ppm
+    }
+
+    /// An RGB PNG image, one pixel per square. PNGs can't be empty, so check `area()` has some
+    /// squares first.
+    pub fn png(&self) -> Vec<u8> {
+        let (area, pixels) = self.pixels();
+        png::encode(area.width as u32, area.height as u32, &pixels)
+    }
+}
+
+/// A bright, stable colour for each claim id
+fn claim_colour(id: usize) -> Rgb {
+    let hash = (id as u32).wrapping_mul(0x9e37_79b1);
+    let channel = |shift: u32| 64 + ((hash >> shift) & 0xff) as u8 % 160;
+    [channel(24), channel(16), channel(8)]
+}
+
+/// Just enough PNG to write uncompressed RGB images, without pulling in a dependency
+mod png {
+    const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+    /// the most a stored deflate block can hold
+    const MAX_BLOCK: usize = 65535;
+
+    pub fn encode(width: u32, height: u32, pixels: &[[u8; 3]]) -> Vec<u8> {
+        // every row starts with filter type 0 (none)
+        let mut raw = Vec::with_capacity(pixels.len() * 3 + height as usize);
+        for row in pixels.chunks(width.max(1) as usize) {
+            raw.push(0);
+            raw.extend(row.iter().flatten());
+        }
+
+        let mut header = Vec::new();
+        header.extend(&width.to_be_bytes());
+        header.extend(&height.to_be_bytes());
+        // 8 bits per channel, RGB, deflate, standard filters, no interlacing
+        header.extend(&[8, 2, 0, 0, 0]);
+
+        let mut png = SIGNATURE.to_vec();
+        chunk(&mut png, b"IHDR", &header);
+        chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+        png.extend(&(data.len() as u32).to_be_bytes());
+        let start = png.len();
+        png.extend(kind);
+        png.extend(data);
+        let crc = crc32(&png[start..]);
+        png.extend(&crc.to_be_bytes());
+    }
+
+    /// A zlib stream of uncompressed deflate blocks
+    fn zlib_stored(data: &[u8]) -> Vec<u8> {
+        // deflate with a 32K window, no preset dictionary, checksummed header
+        let mut zlib = vec![0x78, 0x01];
+        let mut blocks = data.chunks(MAX_BLOCK).peekable();
+        if blocks.peek().is_none() {
+            zlib.extend(&[1, 0, 0, 0xff, 0xff]);
+        }
+        while let Some(block) = blocks.next() {
+            let last = blocks.peek().is_none();
+            zlib.push(u8::from(last));
+            let len = block.len() as u16;
+            zlib.extend(&len.to_le_bytes());
+            zlib.extend(&(!len).to_le_bytes());
+            zlib.extend(block);
+        }
+        zlib.extend(&adler32(data).to_be_bytes());
+        zlib
+    }
+
+    pub fn crc32(data: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for &byte in data {
+            crc ^= u32::from(byte);
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0xedb8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        !crc
+    }
+
+    pub fn adler32(data: &[u8]) -> u32 {
+        let (mut a, mut b) = (1u32, 0u32);
+        for &byte in data {
+            a = (a + u32::from(byte)) % 65521;
+            b = (b + a) % 65521;
+        }
+        (b << 16) | a
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::parse;
+    use super::*;
+
+    fn example() -> Vec<Claim> {
+        parse("#1 @ 1,3: 4x4\n#2 @ 3,1: 4x4\n#3 @ 5,5: 2x2").unwrap()
+    }
+
+    #[test]
+    fn ascii_by_depth() {
+        let claims = example();
+        assert_eq!(
+            Renderer::new(&claims).highlight_uncontended(true).ascii(),
+            "\
+.......
+...1111
+...1111
+.112211
+.112211
+.1111@@
+.1111@@
+"
+        );
+    }
+
+    #[test]
+    fn ascii_by_claim_cropped() {
+        let claims = example();
+        let renderer = Renderer::new(&claims)
+            .colouring(Colouring::Claim)
+            .crop(Rect::new(2, 2, 6, 4));
+        assert_eq!(renderer.ascii(), ".cccc.\nb##cc.\nb##cc.\nbbbdd.\n");
+        assert_eq!(
+            renderer.highlight_uncontended(true).ascii(),
+            ".cccc.\nb##cc.\nb##cc.\nbbbDD.\n"
+        );
+    }
+
+    #[test]
+    fn ppm_has_a_pixel_per_square() {
+        let claims = example();
+        let ppm = Renderer::new(&claims).crop(Rect::new(3, 3, 2, 1)).ppm();
+        let header = b"P6\n2 1\n255\n";
+        assert_eq!(&ppm[..header.len()], header);
+        assert_eq!(&ppm[header.len()..], &[HEAT[2], HEAT[2]].concat()[..]);
+    }
+
+    #[test]
+    fn area_reaches_the_furthest_claim() {
+        assert_eq!(Renderer::new(&example()).area(), Rect::new(0, 0, 7, 7));
+        assert_eq!(Renderer::new(&[]).area().area(), 0);
+        assert_eq!(Renderer::new(&[]).ascii(), "");
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(png::crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(png::adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn png_round_trips() {
+        // wide enough to need several stored blocks
+        let claims = parse("#1 @ 0,0: 30000x2\n#2 @ 10,0: 5x5").unwrap();
+        let png = Renderer::new(&claims).colouring(Colouring::Claim).png();
+        let (width, height, raw) = decode(&png);
+        assert_eq!((width, height), (30000, 5));
+
+        let rows: Vec<&[u8]> = raw.chunks(1 + 30000 * 3).collect();
+        assert_eq!(rows.len(), 5);
+        assert!(rows.iter().all(|row| row[0] == 0));
+        assert_eq!(&rows[0][1..4], &claim_colour(1));
+        assert_eq!(&rows[0][31..34], &CONTESTED);
+        assert_eq!(&rows[4][1..4], &BACKGROUND);
+    }
+
+    /// Checks every chunk's CRC and unpacks the stored blocks
+    fn decode(png: &[u8]) -> (u32, u32, Vec<u8>) {
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let mut rest = &png[8..];
+        let mut chunks = Vec::new();
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+            let (body, crc) = rest[4..].split_at(4 + len);
+            assert_eq!(
+                png::crc32(body),
+                u32::from_be_bytes([crc[0], crc[1], crc[2], crc[3]])
+            );
+            chunks.push((&body[..4], &body[4..]));
+            rest = &crc[4..];
+        }
+        let kinds: Vec<&[u8]> = chunks.iter().map(|&(kind, _)| kind).collect();
+        assert_eq!(kinds, vec![&b"IHDR"[..], b"IDAT", b"IEND"]);
+
+        let header = chunks[0].1;
+        let width = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        let height = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+
+        let zlib = chunks[1].1;
+        let mut raw = Vec::new();
+        let mut blocks = &zlib[2..zlib.len() - 4];
+        loop {
+            let last = blocks[0] == 1;
+            let len = u16::from_le_bytes([blocks[1], blocks[2]]);
+            assert_eq!(!len, u16::from_le_bytes([blocks[3], blocks[4]]));
+            raw.extend(&blocks[5..5 + len as usize]);
+            blocks = &blocks[5 + len as usize..];
+            if last {
+                break;
+            }
+        }
+        assert!(blocks.is_empty());
+        assert_eq!(&zlib[zlib.len() - 4..], &png::adler32(&raw).to_be_bytes());
+        (width, height, raw)
+    }
+}
@@ -1,257 +1,333 @@
-#[cfg(test)]
-mod test {
-    use chrono::NaiveDateTime;
-    use chrono::Timelike;
-    use core::fmt::Write;
-    use itertools::Itertools;
-    use regex::Regex;
-    use std::fmt::Debug;
-    use std::fmt::Error;
-    use std::fmt::Formatter;
-    use std::fs;
-    use std::option::Option::Some;
-    use std::slice::Iter;
-    use std::str::FromStr;
-
-    #[test]
-    fn part1() {
-        let guard_reports = guard_reports();
-
-        let sleepiest_guard: &GuardReport = guard_reports
-            .iter()
-            .max_by_key(|report| report.total_minutes_asleep())
-            .unwrap();
-        assert_eq!(sleepiest_guard.guard_id, 761);
+use crate::error::{parse_capture, parse_lines, ParseError};
+use crate::solution::Solution;
+use chrono::NaiveDateTime;
+use core::fmt::Write;
+use itertools::Itertools;
+use regex::Regex;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Error;
+use std::fmt::Formatter;
+use std::slice::Iter;
+use std::str::FromStr;
+
+pub mod validation;
+
+pub use validation::{validate, Anomaly, AnomalyKind, InvalidLog, Mode, ValidatedLog};
+
+pub struct Day4;
+
+impl Solution for Day4 {
+    const DAY: u8 = 4;
+
+    type Input = Vec<LogEntry>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: &str) -> Result<Vec<LogEntry>, ParseError> {
+        parse(input)
+    }
 
-        let sleepiest_minute = sleepiest_guard.sleepiest_minute();
-        assert_eq!(sleepiest_minute, 25);
+    fn part1(log: &Vec<LogEntry>) -> usize {
+        strategy_1(log)
+    }
 
-        let final_result = sleepiest_minute * sleepiest_guard.guard_id as usize;
-        assert_eq!(final_result, 19025);
+    fn part2(log: &Vec<LogEntry>) -> usize {
+        strategy_2(log)
     }
+}
 
-    #[test]
-    fn part2() {
-        let guard_reports = guard_reports()
-            .iter()
-            .map(|r| {
-                let (minute, &count) = r
-                    .cumulative_sleep_histogram()
-                    .iter()
-                    .enumerate()
-                    .max_by_key(|(_, &count)| count)
-                    .unwrap();
-                (r.guard_id, minute, count)
-            })
-            .collect_vec();
-
-        let (id, minute, count) = guard_reports
-            .iter()
-            .max_by_key(|(_, _, count)| count)
-            .unwrap();
-        assert_eq!(*id, 743);
-        assert_eq!(*minute, 32);
-        assert_eq!(*count, 15);
+/// Finds the guard asleep for the most minutes overall, and multiplies their id by the minute
+/// they were most often asleep
+pub fn strategy_1(log: &[LogEntry]) -> usize {
+    let guard_reports = guard_reports(log);
 
-        let final_answer = *id as usize * minute;
-        assert_eq!(final_answer, 23776);
-    }
+    let sleepiest_guard: &GuardReport = guard_reports
+        .iter()
+        .max_by_key(|report| report.total_minutes_asleep())
+        .unwrap();
 
-    type GuardId = u32;
+    let sleepiest_minute = sleepiest_guard.sleepiest_minute();
+    sleepiest_minute * sleepiest_guard.guard_id as usize
+}
 
-    #[derive(Debug, Eq, PartialEq)]
-    struct LogEntry {
-        pub datetime: NaiveDateTime,
-        pub event: Event,
-    }
+/// Finds the guard most frequently asleep on the same minute, and multiplies their id by that
+/// minute
+pub fn strategy_2(log: &[LogEntry]) -> usize {
+    let (id, minute, _count) = guard_reports(log)
+        .iter()
+        .map(|r| {
+            let (minute, &count) = r
+                .cumulative_sleep_histogram()
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, &count)| count)
+                .unwrap();
+            (r.guard_id, minute, count)
+        })
+        .max_by_key(|&(_, _, count)| count)
+        .unwrap();
+
+    id as usize * minute
+}
 
-    #[derive(Debug, Eq, PartialEq)]
-    enum Event {
-        GuardBeginsShift(GuardId),
-        FallsAsleep,
-        WakesUp,
+/// Parses every line of the log, returning entries in chronological order. A log without any
+/// guard beginning a shift has no one to blame, so is rejected.
+pub fn parse(input: &str) -> Result<Vec<LogEntry>, ParseError> {
+    let mut log: Vec<LogEntry> = parse_lines(input)?;
+    if !log
+        .iter()
+        .any(|entry| matches!(entry.event, Event::GuardBeginsShift(_)))
+    {
+        return Err(ParseError::new(
+            "",
+            0,
+            "at least one guard beginning a shift",
+        ));
     }
+    log.sort_by_key(|entry| entry.datetime);
+    Ok(log)
+}
 
-    #[derive(Copy, Clone)]
-    pub struct SleepReport([bool; 60]);
+pub type GuardId = u32;
 
-    impl SleepReport {
-        // TODO(dfox): try IntoIter instead?
-        pub fn iter(&self) -> Iter<bool> {
-            self.0.iter()
-        }
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct LogEntry {
+    pub datetime: NaiveDateTime,
+    pub event: Event,
+}
 
-        pub fn count(&self) -> usize {
-            self.0.iter().filter(|&&b| b).count()
-        }
-    }
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Event {
+    GuardBeginsShift(GuardId),
+    FallsAsleep,
+    WakesUp,
+}
 
-    impl Debug for SleepReport {
-        fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-            for &asleep in self.0.iter() {
-                f.write_char(if asleep { '#' } else { '.' })?;
-            }
-            Ok(())
-        }
+#[derive(Copy, Clone)]
+pub struct SleepReport([bool; 60]);
+
+impl SleepReport {
+    // TODO(dfox): try IntoIter instead?
+    pub fn iter(&self) -> Iter<'_, bool> {
+        self.0.iter()
     }
 
-    #[derive(Copy, Clone)]
-    struct GuardShiftReport {
-        pub guard_id: GuardId,
-        /// minutes spent asleep during the midnight hour
-        pub asleep_minutes: SleepReport,
+    pub fn count(&self) -> usize {
+        self.0.iter().filter(|&&b| b).count()
     }
+}
 
-    impl Debug for GuardShiftReport {
-        fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-            f.write_char('#')?;
-            self.guard_id.fmt(f)?;
-            f.write_str(": ")?;
-            self.asleep_minutes.fmt(f)?;
-            self.asleep_minutes.count().fmt(f)?;
-            Ok(())
+impl Debug for SleepReport {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        for &asleep in self.0.iter() {
+            f.write_char(if asleep { '#' } else { '.' })?;
         }
+        Ok(())
     }
+}
+
+#[derive(Copy, Clone)]
+pub struct GuardShiftReport {
+    pub guard_id: GuardId,
+    /// minutes spent asleep during the midnight hour
+    pub asleep_minutes: SleepReport,
+}
 
-    #[derive(Debug)]
-    struct GuardReport {
-        pub guard_id: GuardId,
-        pub reports: Vec<SleepReport>,
+impl Debug for GuardShiftReport {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.write_char('#')?;
+        self.guard_id.fmt(f)?;
+        f.write_str(": ")?;
+        self.asleep_minutes.fmt(f)?;
+        self.asleep_minutes.count().fmt(f)?;
+        Ok(())
     }
+}
 
-    impl GuardReport {
-        pub fn total_minutes_asleep(&self) -> usize {
-            self.reports.iter().map(|r| r.count()).sum()
-        }
+#[derive(Debug)]
+pub struct GuardReport {
+    pub guard_id: GuardId,
+    pub reports: Vec<SleepReport>,
+}
 
-        pub fn cumulative_sleep_histogram(&self) -> [u32; 60] {
-            let mut histogram: [u32; 60] = [0; 60];
+impl GuardReport {
+    pub fn total_minutes_asleep(&self) -> usize {
+        self.reports.iter().map(|r| r.count()).sum()
+    }
 
-            for report in &self.reports {
-                for (min, &asleep) in report.iter().enumerate() {
-                    if asleep {
-                        histogram[min] += 1
-                    }
+    pub fn cumulative_sleep_histogram(&self) -> [u32; 60] {
+        let mut histogram: [u32; 60] = [0; 60];
+
+        for report in &self.reports {
+            for (min, &asleep) in report.iter().enumerate() {
+                if asleep {
+                    histogram[min] += 1
                 }
             }
-
-            histogram
         }
 
-        pub fn sleepiest_minute(&self) -> usize {
-            self.cumulative_sleep_histogram()
-                .iter()
-                .enumerate()
-                .max_by_key(|(_minute, &asleep_count)| asleep_count)
-                .unwrap()
-                .0
-        }
+        histogram
     }
 
-    fn guard_shift_reports(entries: &[LogEntry]) -> Vec<GuardShiftReport> {
-        let mut result = vec![];
-
-        let mut guard_id = None;
-        let mut asleep_since = None;
-        let mut asleep_minutes = [false; 60];
-
-        for entry in entries {
-            let current_time = entry.datetime.time().minute() as usize;
-            match entry.event {
-                Event::GuardBeginsShift(id) => {
-                    assert_eq!(asleep_since, None);
-                    if let Some(prev_guard_id) = guard_id {
-                        result.push(GuardShiftReport {
-                            guard_id: prev_guard_id,
-                            asleep_minutes: SleepReport(asleep_minutes),
-                        });
-                        asleep_minutes = [false; 60];
-                    }
+    pub fn sleepiest_minute(&self) -> usize {
+        self.cumulative_sleep_histogram()
+            .iter()
+            .enumerate()
+            .max_by_key(|(_minute, &asleep_count)| asleep_count)
+            .unwrap()
+            .0
+    }
+}
 
-                    guard_id = Some(id);
-                }
-                Event::FallsAsleep => {
-                    assert_eq!(asleep_since, None);
-                    asleep_since = Some(current_time);
+/// One report per shift, after leniently repairing any anomalies in the log
+pub fn guard_shift_reports(entries: &[LogEntry]) -> Vec<GuardShiftReport> {
+    let mut result = vec![];
+
+    let mut guard_id = None;
+    let mut asleep_since = None;
+    let mut asleep_minutes = [false; 60];
+
+    for entry in validation::repair(entries).0 {
+        // repairs guarantee sleep and wake events are in the midnight hour, with a guard on duty
+        let current_time = validation::minute(entry.datetime);
+        match entry.event {
+            Event::GuardBeginsShift(id) => {
+                if let Some(prev_guard_id) = guard_id {
+                    result.push(GuardShiftReport {
+                        guard_id: prev_guard_id,
+                        asleep_minutes: SleepReport(asleep_minutes),
+                    });
+                    asleep_minutes = [false; 60];
                 }
-                Event::WakesUp => {
-                    for minute in asleep_since.unwrap()..current_time {
-                        asleep_minutes[minute] = true;
+
+                guard_id = Some(id);
+            }
+            Event::FallsAsleep => asleep_since = current_time,
+            Event::WakesUp => {
+                if let (Some(since), Some(now)) = (asleep_since.take(), current_time) {
+                    for asleep in &mut asleep_minutes[since..now.max(since)] {
+                        *asleep = true;
                     }
-                    asleep_since = None
                 }
             }
         }
+    }
 
-        if let Some(prev_guard_id) = guard_id {
-            result.push(GuardShiftReport {
-                guard_id: prev_guard_id,
-                asleep_minutes: SleepReport(asleep_minutes),
-            });
-        }
-
-        result
+    if let Some(prev_guard_id) = guard_id {
+        result.push(GuardShiftReport {
+            guard_id: prev_guard_id,
+            asleep_minutes: SleepReport(asleep_minutes),
+        });
     }
 
-    impl FromStr for Event {
-        type Err = ();
-
-        fn from_str(s: &str) -> Result<Self, <Self as FromStr>::Err> {
-            if s == "falls asleep" {
-                return Ok(Event::FallsAsleep);
-            } else if s == "wakes up" {
-                return Ok(Event::WakesUp);
-            } else {
-                lazy_static! {
-                    static ref RE: Regex =
-                        Regex::new(r"^Guard #(?P<id>\d+) begins shift$").unwrap();
-                }
-                let captures = RE.captures(s).unwrap();
-                let id: u32 = captures.name("id").unwrap().as_str().parse().unwrap();
-                return Ok(Event::GuardBeginsShift(id));
+    result
+}
+
+/// Groups every shift worked by the same guard into a single report
+pub fn guard_reports(log: &[LogEntry]) -> Vec<GuardReport> {
+    guard_shift_reports(log)
+        .iter()
+        .map(|report| (report.guard_id, report))
+        .into_group_map()
+        .iter()
+        .map(|(&guard, vec)| -> GuardReport {
+            GuardReport {
+                guard_id: guard,
+                reports: vec.iter().map(|report| report.asleep_minutes).collect(),
             }
+        })
+        .collect_vec()
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            Event::GuardBeginsShift(id) => write!(f, "Guard #{} begins shift", id),
+            Event::FallsAsleep => f.write_str("falls asleep"),
+            Event::WakesUp => f.write_str("wakes up"),
         }
     }
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(
+            f,
+            "[{}] {}",
+            self.datetime.format("%Y-%m-%d %H:%M"),
+            self.event
+        )
+    }
+}
 
-    impl FromStr for LogEntry {
-        type Err = ();
+impl FromStr for Event {
+    type Err = ParseError;
 
-        fn from_str(line: &str) -> Result<Self, <Self as FromStr>::Err> {
+    fn from_str(s: &str) -> Result<Self, <Self as FromStr>::Err> {
+        if s == "falls asleep" {
+            Ok(Event::FallsAsleep)
+        } else if s == "wakes up" {
+            Ok(Event::WakesUp)
+        } else {
             lazy_static! {
-                static ref RE: Regex = Regex::new(r"^\[(?P<datetime>.+)\] (?P<event>.+)$").unwrap();
+                static ref RE: Regex = Regex::new(r"^Guard #(?P<id>\d+) begins shift$").unwrap();
             }
-            let captures = RE.captures(line).unwrap();
-
-            let datetime = captures.name("datetime").unwrap().as_str();
-
-            Ok(LogEntry {
-                datetime: NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M").unwrap(),
-                event: captures.name("event").unwrap().as_str().parse()?,
-            })
+            let captures = RE.captures(s).ok_or_else(|| {
+                ParseError::new(
+                    s,
+                    0,
+                    "'falls asleep', 'wakes up' or 'Guard #<id> begins shift'",
+                )
+            })?;
+            let id = parse_capture(s, &captures, "id", "a guard id that fits in u32")?;
+            Ok(Event::GuardBeginsShift(id))
         }
     }
+}
 
-    fn guard_reports() -> Vec<GuardReport> {
-        let log: Vec<LogEntry> = fs::read_to_string("day4.txt")
-            .unwrap()
-            .lines()
-            .map(|line| LogEntry::from_str(line).unwrap())
-            .sorted_by_key(|entry| entry.datetime)
-            .collect();
+impl FromStr for LogEntry {
+    type Err = ParseError;
 
-        guard_shift_reports(&log)
-            .iter()
-            .map(|report| (report.guard_id, report))
-            .into_group_map()
-            .iter()
-            .map(|(&guard, vec)| -> GuardReport {
-                GuardReport {
-                    guard_id: guard,
-                    reports: vec.iter().map(|report| report.asleep_minutes).collect(),
-                }
-            })
-            .collect_vec()
+    fn from_str(line: &str) -> Result<Self, <Self as FromStr>::Err> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"^\[(?P<datetime>.+)\] (?P<event>.+)$").unwrap();
+        }
+        let captures = RE.captures(line).ok_or_else(|| {
+            ParseError::new(
+                line,
+                0,
+                "a log entry like '[1518-11-01 00:05] falls asleep'",
+            )
+        })?;
+
+        let datetime = captures.name("datetime").unwrap();
+        let event = captures.name("event").unwrap();
+
+        Ok(LogEntry {
+            datetime: NaiveDateTime::parse_from_str(datetime.as_str(), "%Y-%m-%d %H:%M").map_err(
+                |_| {
+                    ParseError::new(
+                        line,
+                        datetime.start(),
+                        "a timestamp like '1518-11-01 00:05'",
+                    )
+                },
+            )?,
+            event: event
+                .as_str()
+                .parse()
+                .map_err(|e: ParseError| e.within(line, event.start()))?,
+        })
     }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generate::Rng;
+    use crate::input;
+    use chrono::{Duration, NaiveDate};
 
     #[test]
     fn example_input() {
@@ -274,12 +350,7 @@ mod test {
 [1518-11-05 00:45] falls asleep
 [1518-11-05 00:55] wakes up
 "#;
-        let log: Vec<LogEntry> = sample
-            .trim()
-            .lines()
-            .map(|line| LogEntry::from_str(line).unwrap())
-            .sorted_by_key(|entry| entry.datetime)
-            .collect();
+        let log: Vec<LogEntry> = parse(sample.trim()).unwrap();
 
         let string = guard_shift_reports(&log)
             .iter()
@@ -293,4 +364,58 @@ mod test {
 #88: .............................................##########.....10"#;
         assert_eq!(string, expected.trim());
     }
+
+    #[test]
+    fn malformed_entries_are_errors() {
+        let error = LogEntry::from_str("[1518-11-01 00:05] falls awake").unwrap_err();
+        assert_eq!(error.column, 20);
+        assert_eq!(error.text, "[1518-11-01 00:05] falls awake");
+
+        let error = LogEntry::from_str("[1518-13-01 00:05] falls asleep").unwrap_err();
+        assert_eq!(error.column, 2);
+
+        let error = LogEntry::from_str("[1518-11-01 00:05] Guard #x begins shift").unwrap_err();
+        assert_eq!(error.column, 20);
+
+        let error = parse("[1518-11-01 00:05] falls asleep\nwakes up").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+    }
+
+    #[test]
+    fn logs_need_a_guard() {
+        assert!(parse("").is_err());
+        let error = parse("[1518-11-01 00:05] falls asleep\n[1518-11-01 00:07] wakes up");
+        assert_eq!(
+            error.unwrap_err().expected,
+            "at least one guard beginning a shift"
+        );
+
+        // no one sleeping is fine, as every minute ties
+        let log = parse("[1518-11-01 00:00] Guard #10 begins shift").unwrap();
+        assert_eq!(strategy_1(&log), strategy_2(&log));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for line in input::load_day(4).unwrap().lines() {
+            assert_eq!(LogEntry::from_str(line).unwrap().to_string(), line);
+        }
+
+        let rng = &mut Rng::seeded(23);
+        let start = NaiveDate::from_ymd_opt(1518, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        for _ in 0..100 {
+            let entry = LogEntry {
+                datetime: start + Duration::minutes(rng.range(0..=1_000_000)),
+                event: match rng.below(3) {
+                    0 => Event::GuardBeginsShift(rng.below(u32::MAX as usize) as GuardId),
+                    1 => Event::FallsAsleep,
+                    _ => Event::WakesUp,
+                },
+            };
+            assert_eq!(LogEntry::from_str(&entry.to_string()), Ok(entry));
+        }
+    }
 }
@@ -0,0 +1,319 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use chrono::{Duration, NaiveDateTime, Timelike};
+
+use super::{Event, GuardId, LogEntry};
+
+/// How to treat a log with anomalies
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// reject it
+    Strict,
+    /// repair it, by dropping events that make no sense and waking guards who never woke
+    Lenient,
+}
+
+/// Something in a log that doesn't fit the story of guards sleeping on shift
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anomaly {
+    /// when it was noticed
+    pub datetime: NaiveDateTime,
+    /// the guard on duty, if any
+    pub guard_id: Option<GuardId>,
+    pub kind: AnomalyKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnomalyKind {
+    /// falling asleep or waking up before any guard has begun a shift
+    NoGuardOnDuty,
+    /// falling asleep or waking up outside the midnight hour
+    OutsideMidnightHour,
+    /// falling asleep when already asleep
+    AlreadyAsleep { since: NaiveDateTime },
+    /// waking up without having fallen asleep
+    WokeWithoutSleeping,
+    /// still asleep when the next guard begins their shift
+    AsleepAtShiftChange { since: NaiveDateTime },
+    /// still asleep after the midnight hour, or at the end of the log
+    AsleepPastMidnightHour { since: NaiveDateTime },
+}
+
+/// A log which makes sense, and what had to be repaired to get it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatedLog {
+    pub entries: Vec<LogEntry>,
+    /// always empty in strict mode
+    pub anomalies: Vec<Anomaly>,
+}
+
+/// Every anomaly in a log rejected by strict validation
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidLog(pub Vec<Anomaly>);
+
+/// Checks a chronological log (as returned by `parse`), failing on any anomaly in strict mode
+/// and repairing them in lenient mode
+pub fn validate(log: &[LogEntry], mode: Mode) -> Result<ValidatedLog, InvalidLog> {
+    let (entries, anomalies) = repair(log);
+    match mode {
+        Mode::Strict if !anomalies.is_empty() => Err(InvalidLog(anomalies)),
+        Mode::Strict => Ok(ValidatedLog {
+            entries,
+            anomalies: vec![],
+        }),
+        Mode::Lenient => Ok(ValidatedLog { entries, anomalies }),
+    }
+}
+
+/// Walks the log as a state machine, returning the repaired log and everything repaired
+pub(super) fn repair(log: &[LogEntry]) -> (Vec<LogEntry>, Vec<Anomaly>) {
+    let mut entries = Vec::with_capacity(log.len());
+    let mut anomalies = Vec::new();
+    let mut guard_id = None;
+    let mut asleep_since: Option<NaiveDateTime> = None;
+
+    for entry in log {
+        let anomaly = |guard_id, kind| Anomaly {
+            datetime: entry.datetime,
+            guard_id,
+            kind,
+        };
+
+        // a nap which should have ended by now
+        if let Some(since) = asleep_since {
+            let woke = match entry.event {
+                Event::GuardBeginsShift(_) => {
+                    anomalies.push(anomaly(
+                        guard_id,
+                        AnomalyKind::AsleepAtShiftChange { since },
+                    ));
+                    Some(entry.datetime.min(end_of_midnight_hour(since)))
+                }
+                _ if entry.datetime > end_of_midnight_hour(since) => {
+                    let kind = AnomalyKind::AsleepPastMidnightHour { since };
+                    anomalies.push(anomaly(guard_id, kind));
+                    Some(end_of_midnight_hour(since))
+                }
+                _ => None,
+            };
+            if let Some(datetime) = woke {
+                entries.push(LogEntry {
+                    datetime,
+                    event: Event::WakesUp,
+                });
+                asleep_since = None;
+            }
+        }
+
+        let kind = match entry.event {
+            Event::GuardBeginsShift(id) => {
+                guard_id = Some(id);
+                None
+            }
+            _ if guard_id.is_none() => Some(AnomalyKind::NoGuardOnDuty),
+            Event::FallsAsleep => match (asleep_since, minute(entry.datetime)) {
+                (_, None) | (_, Some(60)) => Some(AnomalyKind::OutsideMidnightHour),
+                (Some(since), _) => Some(AnomalyKind::AlreadyAsleep { since }),
+                (None, _) => {
+                    asleep_since = Some(entry.datetime);
+                    None
+                }
+            },
+            Event::WakesUp => match (asleep_since, minute(entry.datetime)) {
+                (_, None) => Some(AnomalyKind::OutsideMidnightHour),
+                (None, _) => Some(AnomalyKind::WokeWithoutSleeping),
+                (Some(_), _) => {
+                    asleep_since = None;
+                    None
+                }
+            },
+        };
+        match kind {
+            Some(kind) => anomalies.push(anomaly(guard_id, kind)),
+            None => entries.push(entry.clone()),
+        }
+    }
+
+    if let Some(since) = asleep_since {
+        let datetime = end_of_midnight_hour(since);
+        anomalies.push(Anomaly {
+            datetime,
+            guard_id,
+            kind: AnomalyKind::AsleepPastMidnightHour { since },
+        });
+        entries.push(LogEntry {
+            datetime,
+            event: Event::WakesUp,
+        });
+    }
+
+    (entries, anomalies)
+}
+
+/// Minutes past midnight, up to 60 for 01:00 (when guards still asleep are woken), or `None`
+/// outside the midnight hour
+pub(super) fn minute(datetime: NaiveDateTime) -> Option<usize> {
+    match (datetime.hour(), datetime.minute()) {
+        (0, minute) => Some(minute as usize),
+        (1, 0) => Some(60),
+        _ => None,
+    }
+}
+
+fn end_of_midnight_hour(datetime: NaiveDateTime) -> NaiveDateTime {
+    datetime.date().and_hms_opt(0, 0, 0).unwrap() + Duration::hours(1)
+}
+
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "[{}] ", self.datetime.format("%Y-%m-%d %H:%M"))?;
+        if let Some(id) = self.guard_id {
+            write!(f, "guard #{} ", id)?;
+        }
+        let since = |since: &NaiveDateTime| since.format("%Y-%m-%d %H:%M");
+        match &self.kind {
+            AnomalyKind::NoGuardOnDuty => write!(f, "event before any guard is on duty"),
+            AnomalyKind::OutsideMidnightHour => write!(f, "event outside the midnight hour"),
+            AnomalyKind::AlreadyAsleep { since: s } => {
+                write!(f, "fell asleep while asleep since {}", since(s))
+            }
+            AnomalyKind::WokeWithoutSleeping => write!(f, "woke up without falling asleep"),
+            AnomalyKind::AsleepAtShiftChange { since: s } => {
+                write!(f, "asleep at shift change since {}", since(s))
+            }
+            AnomalyKind::AsleepPastMidnightHour { since: s } => {
+                write!(f, "asleep past the midnight hour since {}", since(s))
+            }
+        }
+    }
+}
+
+impl Display for InvalidLog {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} anomalies in guard log", self.0.len())?;
+        for anomaly in &self.0 {
+            write!(f, "\n{}", anomaly)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for InvalidLog {}
+
+#[cfg(test)]
+mod test {
+    use super::super::{guard_shift_reports, parse};
+    use super::*;
+    use crate::generate;
+    use crate::generate::Rng;
+
+    fn lines(entries: &[LogEntry]) -> Vec<String> {
+        entries.iter().map(LogEntry::to_string).collect()
+    }
+
+    #[test]
+    fn reports_and_repairs_anomalies() {
+        let log = parse(
+            "\
+[1518-11-01 00:01] wakes up
+[1518-11-01 00:02] Guard #10 begins shift
+[1518-11-01 00:05] falls asleep
+[1518-11-01 00:07] falls asleep
+[1518-11-01 00:25] wakes up
+[1518-11-01 00:30] wakes up
+[1518-11-01 00:40] falls asleep
+[1518-11-01 23:58] Guard #99 begins shift
+[1518-11-02 00:10] falls asleep
+[1518-11-02 01:10] wakes up
+[1518-11-02 23:59] falls asleep",
+        )
+        .unwrap();
+
+        let error = validate(&log, Mode::Strict).unwrap_err();
+        let anomalies: Vec<String> = error.0.iter().map(Anomaly::to_string).collect();
+        assert_eq!(
+            anomalies,
+            vec![
+                "[1518-11-01 00:01] event before any guard is on duty",
+                "[1518-11-01 00:07] guard #10 fell asleep while asleep since 1518-11-01 00:05",
+                "[1518-11-01 00:30] guard #10 woke up without falling asleep",
+                "[1518-11-01 23:58] guard #10 asleep at shift change since 1518-11-01 00:40",
+                "[1518-11-02 01:10] guard #99 asleep past the midnight hour since 1518-11-02 00:10",
+                "[1518-11-02 01:10] guard #99 event outside the midnight hour",
+                "[1518-11-02 23:59] guard #99 event outside the midnight hour",
+            ]
+        );
+        assert!(error.to_string().starts_with("7 anomalies in guard log\n"));
+
+        let repaired = validate(&log, Mode::Lenient).unwrap();
+        assert_eq!(repaired.anomalies, error.0);
+        assert_eq!(
+            lines(&repaired.entries),
+            vec![
+                "[1518-11-01 00:02] Guard #10 begins shift",
+                "[1518-11-01 00:05] falls asleep",
+                "[1518-11-01 00:25] wakes up",
+                "[1518-11-01 00:40] falls asleep",
+                "[1518-11-01 01:00] wakes up",
+                "[1518-11-01 23:58] Guard #99 begins shift",
+                "[1518-11-02 00:10] falls asleep",
+                "[1518-11-02 01:00] wakes up",
+            ]
+        );
+        assert_eq!(
+            validate(&repaired.entries, Mode::Strict).unwrap().entries,
+            repaired.entries
+        );
+    }
+
+    #[test]
+    fn shift_change_within_the_hour_wakes_the_sleeper() {
+        let log = parse(
+            "\
+[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:05] falls asleep
+[1518-11-01 00:30] Guard #99 begins shift",
+        )
+        .unwrap();
+        let repaired = validate(&log, Mode::Lenient).unwrap();
+        assert_eq!(
+            repaired.entries[2].to_string(),
+            "[1518-11-01 00:30] wakes up"
+        );
+        assert_eq!(repaired.anomalies[0].guard_id, Some(10));
+        assert_eq!(guard_shift_reports(&log)[0].asleep_minutes.count(), 25);
+    }
+
+    #[test]
+    fn generated_logs_are_valid() {
+        for seed in 0..10 {
+            let input = generate::day4(&mut Rng::seeded(seed), 30, 5);
+            let log = parse(&input).unwrap();
+            assert_eq!(validate(&log, Mode::Strict).unwrap().entries, log);
+        }
+    }
+
+    #[test]
+    fn repairs_are_valid() {
+        for seed in 0..20 {
+            let rng = &mut Rng::seeded(seed);
+            let mut log = parse(&generate::day4(rng, 30, 5)).unwrap();
+            // lose and duplicate some entries
+            for _ in 0..10 {
+                let i = rng.below(log.len());
+                if rng.chance(1, 2) {
+                    log.remove(i);
+                } else {
+                    log.insert(i, log[i].clone());
+                }
+            }
+
+            let repaired = validate(&log, Mode::Lenient).unwrap();
+            let revalidated = validate(&repaired.entries, Mode::Strict).unwrap();
+            assert_eq!(revalidated.entries, repaired.entries);
+            guard_shift_reports(&log);
+        }
+    }
+}
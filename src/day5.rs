@@ -1,70 +1,95 @@
-#[cfg(test)]
-mod test {
-    use std::collections::HashSet;
-    use std::fs;
+use crate::error::ParseError;
+use crate::solution::Solution;
 
-    static DELETED: u8 = 48; // 0
+static DELETED: u8 = 48; // 0
 
-    #[test]
-    fn part1() {
-        let mut bytes: Vec<u8> = fs::read("day5.txt").unwrap();
-        let squished: Vec<u8> = recursively_collapse(&mut bytes);
+pub struct Day5;
+
+impl Solution for Day5 {
+    const DAY: u8 = 5;
+
+    type Input = String;
+    type Part1 = usize;
+    type Part2 = usize;
 
-        let x = std::str::from_utf8(&squished).unwrap();
-        assert_eq!(x.trim().len(), 11194);
+    fn parse(input: &str) -> Result<String, ParseError> {
+        parse(input)
     }
 
-    #[test]
-    fn part2() {
-        let string = fs::read_to_string("day5.txt").unwrap();
-        let mut best = string.len();
-
-        for letter in b'A'..b'Z' {
-            let cleansed = string
-                .trim()
-                .replace(letter as char, "")
-                .replace((letter as char).to_ascii_lowercase(), "");
-            let vec = recursively_collapse(cleansed.as_bytes());
-            let candidate = dbg!(vec.len());
-            if candidate < best {
-                best = candidate;
-            }
-        }
+    fn part1(polymer: &String) -> usize {
+        recursively_collapse(polymer.as_bytes()).len()
+    }
+
+    fn part2(polymer: &String) -> usize {
+        shortest_without_one_unit(polymer)
+    }
+}
 
-        assert_eq!(best, 4178);
+/// A polymer is a single line of ASCII letters, each one a unit
+pub fn parse(input: &str) -> Result<String, ParseError> {
+    let polymer = input.trim();
+    match polymer.find(|c: char| !c.is_ascii_alphabetic()) {
+        Some(offset) => Err(ParseError::new(
+            polymer,
+            offset,
+            "a polymer of ASCII letters",
+        )),
+        None => Ok(polymer.to_string()),
     }
+}
 
-    fn recursively_collapse(entry: &[u8]) -> Vec<u8> {
-        println!("Starting with {} bytes", &entry.len());
-
-        let mut bytes: Vec<u8> = Vec::from(entry);
-        loop {
-            let mut changes = false;
-            for i in 0..bytes.len() - 1 {
-                if should_collapse(bytes[i], bytes[i + 1]) {
-                    bytes[i] = DELETED;
-                    bytes[i + 1] = DELETED;
-                    changes = true
-                }
-            }
+/// Tries removing every unit type (both polarities) and reports the shortest fully reacted polymer
+pub fn shortest_without_one_unit(polymer: &str) -> usize {
+    let mut best = polymer.len();
 
-            if !changes {
-                break;
-            }
+    for letter in b'A'..=b'Z' {
+        let cleansed = polymer.replace([letter as char, (letter as char).to_ascii_lowercase()], "");
+        let candidate = recursively_collapse(cleansed.as_bytes()).len();
+        if candidate < best {
+            best = candidate;
+        }
+    }
 
-            let mut new_vec = Vec::new();
-            for i in 0..bytes.len() {
-                if bytes[i] != DELETED {
-                    new_vec.push(bytes[i]);
-                }
+    best
+}
+
+pub fn recursively_collapse(entry: &[u8]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::from(entry);
+    loop {
+        let mut changes = false;
+        for i in 1..bytes.len() {
+            if should_collapse(bytes[i - 1], bytes[i]) {
+                bytes[i - 1] = DELETED;
+                bytes[i] = DELETED;
+                changes = true
             }
-            bytes = new_vec;
         }
-        bytes
+
+        if !changes {
+            break;
+        }
+
+        bytes.retain(|&byte| byte != DELETED);
     }
+    bytes
+}
+
+#[inline]
+fn should_collapse(left: u8, right: u8) -> bool {
+    left.eq_ignore_ascii_case(&right)
+        && (char::from(left).is_lowercase() ^ char::from(right).is_lowercase())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generate;
+    use crate::generate::Rng;
+    use crate::input;
+    use std::collections::HashSet;
 
     fn alphabet() -> HashSet<char> {
-        let string = fs::read_to_string("day5.txt").unwrap().to_uppercase();
+        let string = input::load_day(5).unwrap().to_uppercase();
         string.chars().collect()
     }
 
@@ -74,9 +99,43 @@ mod test {
         assert_eq!(all_known_bytes.len(), 27); // there's a newline on the end!
     }
 
-    #[inline]
-    fn should_collapse(left: u8, right: u8) -> bool {
-        left.eq_ignore_ascii_case(&right)
-            && (char::from(left).is_lowercase() ^ char::from(right).is_lowercase())
+    #[test]
+    fn polymers_only_contain_letters() {
+        assert_eq!(
+            parse("dabAcCaCBAcCcaDA\n"),
+            Ok("dabAcCaCBAcCcaDA".to_string())
+        );
+        assert_eq!(parse("dabA cCa").unwrap_err().column, 5);
+    }
+
+    /// Reacts the polymer in a single pass, using the result so far as a stack
+    fn react(polymer: &[u8]) -> Vec<u8> {
+        let mut reacted: Vec<u8> = Vec::with_capacity(polymer.len());
+        for &unit in polymer {
+            match reacted.last() {
+                Some(&last) if should_collapse(last, unit) => {
+                    reacted.pop();
+                }
+                _ => reacted.push(unit),
+            }
+        }
+        reacted
+    }
+
+    #[test]
+    fn reacted_polymers_contain_no_reactive_pairs() {
+        for seed in 0..200 {
+            let polymer = generate::day5(&mut Rng::seeded(seed), 200);
+            let reacted = recursively_collapse(polymer.as_bytes());
+
+            assert!(
+                reacted
+                    .windows(2)
+                    .all(|pair| !should_collapse(pair[0], pair[1])),
+                "seed {}",
+                seed
+            );
+            assert_eq!(reacted, react(polymer.as_bytes()), "seed {}", seed);
+        }
     }
 }
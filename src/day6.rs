@@ -1,90 +1,140 @@
-#[cfg(test)]
-mod test {
-    use itertools::Itertools;
-    use std::collections::HashMap;
-    use std::fs;
+use crate::error::ParseError;
+use crate::solution::Solution;
+use itertools::Itertools;
+use std::collections::HashMap;
 
-    type Point = (u32, u32);
+pub type Point = (u32, u32);
 
-    #[test]
-    fn example() {
-        let input = vec![(1, 1), (1, 6), (8, 3), (3, 4), (5, 5), (8, 9)];
-        assert_eq!(solve_part1(&input), 17);
-        assert_eq!(solve_part2(&input, 32), 16);
+pub struct Day6;
+
+impl Solution for Day6 {
+    const DAY: u8 = 6;
+
+    type Input = Vec<Point>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: &str) -> Result<Vec<Point>, ParseError> {
+        parse(input)
     }
 
-    #[test]
-    fn real_data() {
-        let input = input();
-        assert_eq!(solve_part1(&input), 3620);
-        assert_eq!(solve_part2(&input, 10_000), 39930);
+    fn part1(points: &Vec<Point>) -> usize {
+        solve_part1(points)
     }
 
-    fn solve_part1(input: &[Point]) -> usize {
-        let grid = grid(input);
+    fn part2(points: &Vec<Point>) -> usize {
+        solve_part2(points, 10_000)
+    }
+}
 
-        // figure out who owns each grid point
-        let mut owned_points: HashMap<Point, Vec<Point>> = HashMap::new();
-        for grid_point in grid {
-            let owner = find_nearest(&grid_point, &input);
-            owned_points.entry(owner).or_default().push(grid_point);
-        }
+pub fn solve_part1(input: &[Point]) -> usize {
+    let grid = grid(input);
 
-        let (_point, owned) = owned_points
-            .iter()
-            .max_by_key(|(_, vec)| vec.len())
-            .unwrap();
-        owned.len()
+    // figure out who owns each grid point
+    let mut owned_points: HashMap<Point, Vec<Point>> = HashMap::new();
+    for grid_point in grid {
+        let owner = find_nearest(&grid_point, input);
+        owned_points.entry(owner).or_default().push(grid_point);
     }
 
-    fn solve_part2(input: &[Point], cutoff: u32) -> usize {
-        let grid = grid(input);
-
-        let mut safest_points = Vec::new();
-        for grid_point in grid {
-            let dist_to_all_points = input
-                .iter()
-                .map(|input| manhattan(&grid_point, input))
-                .sum::<u32>();
-            if dist_to_all_points < cutoff {
-                safest_points.push(grid_point);
-            }
-        }
+    owned_points.values().map(Vec::len).max().unwrap_or(0)
+}
+
+pub fn solve_part2(input: &[Point], cutoff: u32) -> usize {
+    let grid = grid(input);
 
-        safest_points.len()
+    let mut safest_points = Vec::new();
+    for grid_point in grid {
+        let dist_to_all_points = input
+            .iter()
+            .map(|input| manhattan(&grid_point, input))
+            .sum::<u32>();
+        if dist_to_all_points < cutoff {
+            safest_points.push(grid_point);
+        }
     }
 
-    fn grid(input: &[Point]) -> Vec<Point> {
-        // find bounds, to allow us to brute force
-        let (xs, ys): (Vec<u32>, Vec<u32>) = input.iter().cloned().unzip();
-        let range_x = *xs.iter().min().unwrap()..=*xs.iter().max().unwrap();
-        let range_y = *ys.iter().min().unwrap()..=*ys.iter().max().unwrap();
+    safest_points.len()
+}
 
+fn grid(input: &[Point]) -> Vec<Point> {
+    // find bounds, to allow us to brute force
+    let (xs, ys): (Vec<u32>, Vec<u32>) = input.iter().cloned().unzip();
+    match (
+        xs.iter().minmax().into_option(),
+        ys.iter().minmax().into_option(),
+    ) {
         // compute grid to exactly cover our input points
-        range_x.cartesian_product(range_y).collect_vec()
+        (Some((&min_x, &max_x)), Some((&min_y, &max_y))) => (min_x..=max_x)
+            .cartesian_product(min_y..=max_y)
+            .collect_vec(),
+        _ => vec![],
     }
+}
 
-    fn find_nearest(home: &Point, others: &[Point]) -> Point {
-        *others
-            .iter()
-            .min_by_key(|&other| manhattan(home, other))
-            .unwrap()
+fn find_nearest(home: &Point, others: &[Point]) -> Point {
+    *others
+        .iter()
+        .min_by_key(|&other| manhattan(home, other))
+        .unwrap()
+}
+
+fn manhattan((x1, y1): &Point, (x2, y2): &Point) -> u32 {
+    x1.abs_diff(*x2) + y1.abs_diff(*y2)
+}
+
+/// Parses every line, of which there must be at least one
+pub fn parse(input: &str) -> Result<Vec<Point>, ParseError> {
+    let points: Vec<Point> = input
+        .lines()
+        .enumerate()
+        .map(|(index, l)| parse_point(l).map_err(|e| e.at_line(index + 1)))
+        .collect::<Result<_, _>>()?;
+    if points.is_empty() {
+        return Err(ParseError::new("", 0, "at least one point"));
     }
+    Ok(points)
+}
+
+/// e.g. `81, 252`
+fn parse_point(l: &str) -> Result<Point, ParseError> {
+    let expected = "a coordinate like '81, 252'";
+    let separator = l
+        .find(", ")
+        .ok_or_else(|| ParseError::new(l, 0, expected))?;
+    let x = l[..separator]
+        .parse()
+        .map_err(|_| ParseError::new(l, 0, expected))?;
+    let y = l[separator + 2..]
+        .parse()
+        .map_err(|_| ParseError::new(l, separator + 2, expected))?;
+    Ok((x, y))
+}
 
-    fn manhattan((x1, y1): &Point, (x2, y2): &Point) -> u32 {
-        let dist_x = if x1 > x2 { x1 - x2 } else { x2 - x1 };
-        let dist_y = if y1 > y2 { y1 - y2 } else { y2 - y1 };
-        dist_x + dist_y
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let input = vec![(1, 1), (1, 6), (8, 3), (3, 4), (5, 5), (8, 9)];
+        assert_eq!(solve_part1(&input), 17);
+        assert_eq!(solve_part2(&input, 32), 16);
     }
 
-    fn input() -> Vec<Point> {
-        fs::read_to_string("day6.txt")
-            .unwrap()
-            .lines()
-            .map(|l| -> Point {
-                let parts: Vec<&str> = l.split(", ").collect();
-                (parts[0].parse().unwrap(), parts[1].parse().unwrap())
-            })
-            .collect()
+    #[test]
+    fn malformed_points_are_errors() {
+        assert_eq!(parse("1, 1\n8, 3"), Ok(vec![(1, 1), (8, 3)]));
+        let error = parse("1, 1\n8,3").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+        let error = parse("1, -1").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 4));
+        assert_eq!(parse("").unwrap_err().expected, "at least one point");
+    }
+
+    #[test]
+    fn no_points_own_nothing() {
+        assert_eq!(solve_part1(&[]), 0);
+        assert_eq!(solve_part2(&[], 32), 0);
     }
 }
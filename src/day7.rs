@@ -1,211 +1,347 @@
-#[cfg(test)]
-mod test {
-    use itertools::Itertools;
-    use regex::Regex;
-    use std::collections::BTreeMap;
-    use std::collections::HashMap;
-    use std::collections::HashSet;
-    use std::fs;
-    use std::io::Error;
-    use std::str::FromStr;
+use crate::error::{parse_lines, ParseError};
+use crate::solution::Solution;
+use itertools::Itertools;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+pub struct Day7;
+
+impl Solution for Day7 {
+    const DAY: u8 = 7;
+
+    type Input = Vec<Dependency>;
+    type Part1 = String;
+    type Part2 = Time;
+
+    fn parse(input: &str) -> Result<Vec<Dependency>, ParseError> {
+        parse(input)
+    }
 
-    #[test]
-    fn example() {
-        let input = sample_input();
-        assert_eq!(solve_part1(&input), "CABDFE".to_string());
+    fn part1(dependencies: &Vec<Dependency>) -> String {
+        solve_part1(dependencies)
     }
 
-    #[test]
-    fn real_data() {
-        let input = real_input();
-        assert_eq!(solve_part1(&input), "BFKEGNOVATIHXYZRMCJDLSUPWQ");
-        assert_eq!(solve_part2(&input), 1020);
+    fn part2(dependencies: &Vec<Dependency>) -> Time {
+        solve_part2(dependencies)
     }
+}
 
-    fn solve_part1(dependencies: &[Dependency]) -> String {
-        let ids = step_ids(&dependencies);
+/// Parses every dependency, rejecting the first which is part of a cycle, as none of the steps
+/// on a cycle could ever begin
+pub fn parse(input: &str) -> Result<Vec<Dependency>, ParseError> {
+    let dependencies: Vec<Dependency> = parse_lines(input)?;
+    let cyclic = dependencies
+        .iter()
+        .position(|dep| depends_on(&dependencies, dep.prereq, dep.step));
+    if let Some(index) = cyclic {
+        let line = input.lines().nth(index).unwrap();
+        return Err(
+            ParseError::new(line, 0, "a dependency which isn't part of a cycle").at_line(index + 1),
+        );
+    }
+    Ok(dependencies)
+}
 
-        // Step A (key) requires C (values) to be complete
-        let mut back_edges = new_edge_map(&ids);
-        for dep in dependencies {
-            back_edges.entry(dep.step).or_default().push(dep.prereq);
+/// Whether `step` has to wait for `prereq`, directly or through other steps
+fn depends_on(dependencies: &[Dependency], step: StepId, prereq: StepId) -> bool {
+    let mut seen = HashSet::new();
+    let mut waiting = vec![step];
+    while let Some(current) = waiting.pop() {
+        for dep in dependencies.iter().filter(|dep| dep.step == current) {
+            if dep.prereq == prereq {
+                return true;
+            }
+            if seen.insert(dep.prereq) {
+                waiting.push(dep.prereq);
+            }
         }
+    }
+    false
+}
 
-        let mut order = Vec::new();
-        while !back_edges.is_empty() {
-            let step = *back_edges
-                .iter()
-                .filter(|(_node, prereqs)| prereqs.is_empty())
-                .next()
-                .expect("No step found with zero prereqs")
-                .0;
+/// The order to do every step in, leaving out any which wait on a cycle (which `parse` rejects)
+pub fn solve_part1(dependencies: &[Dependency]) -> String {
+    let ids = step_ids(dependencies);
 
-            order.push(step);
-            back_edges.remove(&step);
+    // Step A (key) requires C (values) to be complete
+    let mut back_edges = new_edge_map(&ids);
+    for dep in dependencies {
+        back_edges.entry(dep.step).or_default().push(dep.prereq);
+    }
 
-            for (_, prereqs) in &mut back_edges {
-                prereqs.retain(|&prereq| prereq != step);
-            }
+    let mut order = Vec::new();
+    while !back_edges.is_empty() {
+        let step = match back_edges
+            .iter()
+            .find(|(_node, prereqs)| prereqs.is_empty())
+        {
+            Some((&step, _)) => step,
+            // everything left waits on a cycle
+            None => break,
+        };
+
+        order.push(step);
+        back_edges.remove(&step);
+
+        for prereqs in back_edges.values_mut() {
+            prereqs.retain(|&prereq| prereq != step);
         }
-
-        order.iter().collect()
     }
 
-    fn solve_part2(dependencies: &[Dependency]) -> Time {
-        let ids = step_ids(&dependencies);
+    order.iter().collect()
+}
+
+/// The time taken for every step that can be done, which excludes any waiting on a cycle
+pub fn solve_part2(dependencies: &[Dependency]) -> Time {
+    let ids = step_ids(dependencies);
+
+    // Step A (key) requires C (values) to be complete
+    let mut back_edges = new_edge_map(&ids);
+    for dep in dependencies {
+        back_edges.entry(dep.step).or_default().push(dep.prereq);
+    }
 
-        // Step A (key) requires C (values) to be complete
-        let mut back_edges = new_edge_map(&ids);
-        for dep in dependencies {
-            back_edges.entry(dep.step).or_default().push(dep.prereq);
+    let mut workers = WorkerPool::new(5);
+    while !back_edges.is_empty() || workers.in_progress() {
+        // determine next possible work (keys of back_edges are already sorted)
+        let next_steps: Vec<StepId> = back_edges
+            .iter()
+            .filter(|(_, prereqs)| prereqs.is_empty())
+            .map(|(&step, _)| step)
+            .take(workers.available() as usize)
+            .collect();
+        if next_steps.is_empty() && !workers.in_progress() {
+            // everything left waits on a cycle
+            break;
         }
 
-        let mut workers = WorkerPool::new(5);
-        while !back_edges.is_empty() || workers.in_progress() {
-            // determine next possible work (keys of back_edges are already sorted)
-            let next_steps: Vec<StepId> = back_edges
-                .iter()
-                .filter(|(_, prereqs)| prereqs.is_empty())
-                .map(|(&step, _)| step)
-                .take(workers.available() as usize)
-                .collect();
-
-            // track worker utilisation & remove from the graph so nobody else tries to start this work
-            for step in next_steps {
-                workers.begin_work(step, step_duration(step));
-                back_edges.remove(&step);
-            }
+        // track worker utilisation & remove from the graph so nobody else tries to start this work
+        for step in next_steps {
+            workers.begin_work(step, step_duration(step));
+            back_edges.remove(&step);
+        }
 
-            // advance the clock & unblock new tasks if possible
-            if let Some(completed) = workers.tick() {
-                for (_, prereqs) in &mut back_edges {
-                    prereqs.retain(|prereq| !completed.contains(prereq));
-                }
+        // advance the clock & unblock new tasks if possible
+        if let Some(completed) = workers.tick() {
+            for prereqs in back_edges.values_mut() {
+                prereqs.retain(|prereq| !completed.contains(prereq));
             }
         }
+    }
+
+    workers.time()
+}
+
+pub fn step_duration(step: StepId) -> Time {
+    (step as usize - 'A' as usize) + 61
+}
+
+pub type Time = usize;
 
-        workers.time()
+#[derive(Debug)]
+pub struct WorkerPool {
+    time: Time,
+    available_workers: u32,
+    // stores tasks and the clock time when they will be finished
+    in_progress: HashMap<StepId, Time>,
+}
+
+impl WorkerPool {
+    pub fn new(num_workers: u32) -> WorkerPool {
+        WorkerPool {
+            time: 0,
+            available_workers: num_workers,
+            in_progress: HashMap::new(),
+        }
     }
 
-    fn step_duration(step: StepId) -> Time {
-        (step as usize - 'A' as usize) + 61
+    pub fn in_progress(&self) -> bool {
+        !self.in_progress.is_empty()
     }
 
-    #[test]
-    fn test_step_duration() {
-        assert_eq!(step_duration('A'), 61);
-        assert_eq!(step_duration('B'), 62);
-        assert_eq!(step_duration('C'), 63);
-        assert_eq!(step_duration('Z'), 86);
+    pub fn time(&self) -> Time {
+        self.time
     }
 
-    type Time = usize;
+    pub fn available(&self) -> u32 {
+        self.available_workers
+    }
 
-    #[derive(Debug)]
-    struct WorkerPool {
-        time: Time,
-        available_workers: u32,
-        // stores tasks and the clock time when they will be finished
-        in_progress: HashMap<StepId, Time>,
+    pub fn begin_work(&mut self, step_id: StepId, duration: Time) {
+        assert!(
+            self.available() > 0,
+            "tried to begin work but no worker available"
+        );
+        assert!(
+            !self.in_progress.contains_key(&step_id),
+            "Can't schedule work already running"
+        );
+        self.available_workers -= 1;
+        self.in_progress.insert(step_id, self.time + duration);
     }
 
-    impl WorkerPool {
-        fn new(num_workers: u32) -> WorkerPool {
-            WorkerPool {
-                time: 0,
-                available_workers: num_workers,
-                in_progress: HashMap::new(),
-            }
-        }
+    // advances the clock and and work that was completed (if any)
+    pub fn tick(&mut self) -> Option<HashSet<StepId>> {
+        self.time += 1;
 
-        fn in_progress(&self) -> bool {
-            !self.in_progress.is_empty()
+        // figure out which tasks have finished
+        let mut completed = HashSet::new();
+        for (&step, step_finished_time) in &self.in_progress {
+            if step_finished_time == &self.time {
+                completed.insert(step);
+            }
         }
-
-        fn time(&self) -> Time {
-            self.time
+        if completed.is_empty() {
+            return None;
         }
 
-        fn available(&self) -> u32 {
-            self.available_workers
+        for step in &completed {
+            self.in_progress.remove(step);
         }
 
-        fn begin_work(&mut self, step_id: StepId, duration: Time) {
-            assert!(
-                self.available() > 0,
-                "tried to begin work but no worker available"
-            );
-            assert!(
-                !self.in_progress.contains_key(&step_id),
-                "Can't schedule work already running"
-            );
-            self.available_workers -= 1;
-            self.in_progress.insert(step_id, self.time + duration);
-        }
+        self.available_workers += completed.len() as u32;
+        Some(completed)
+    }
+}
 
-        // advances the clock and and work that was completed (if any)
-        fn tick(&mut self) -> Option<HashSet<StepId>> {
-            self.time += 1;
+// ensures all node ids appear
+fn new_edge_map(ids: &[StepId]) -> BTreeMap<StepId, Vec<StepId>> {
+    let mut map: BTreeMap<StepId, Vec<StepId>> = BTreeMap::new();
+    for &id in ids {
+        map.insert(id, Vec::new());
+    }
+    map
+}
 
-            // figure out which tasks have finished
-            let mut completed = HashSet::new();
-            for (&step, step_finished_time) in &self.in_progress {
-                if step_finished_time == &self.time {
-                    completed.insert(step);
-                }
-            }
-            if completed.is_empty() {
-                return None;
-            }
+pub type StepId = char;
 
-            for step in &completed {
-                self.in_progress.remove(&step);
-            }
+#[derive(Debug, PartialEq)]
+pub struct Dependency {
+    pub step: StepId,
+    pub prereq: StepId,
+}
 
-            self.available_workers += completed.len() as u32;
-            Some(completed)
-        }
+impl Display for Dependency {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Step {} must be finished before step {} can begin.",
+            self.prereq, self.step
+        )
     }
+}
 
-    // ensures all node ids appear
-    fn new_edge_map(ids: &[StepId]) -> BTreeMap<StepId, Vec<StepId>> {
-        let mut map: BTreeMap<StepId, Vec<StepId>> = BTreeMap::new();
-        for &id in ids {
-            map.insert(id, Vec::new());
+impl FromStr for Dependency {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, <Self as FromStr>::Err> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r"^Step ([A-Z]) must be finished before step ([A-Z]) can begin\.$")
+                    .unwrap();
         }
-        map
+
+        let captures = RE.captures(s).ok_or_else(|| {
+            ParseError::new(
+                s,
+                0,
+                "a dependency like 'Step C must be finished before step A can begin.'",
+            )
+        })?;
+        Ok(Dependency {
+            prereq: captures[1].chars().next().unwrap(),
+            step: captures[2].chars().next().unwrap(),
+        })
     }
+}
+
+pub fn step_ids(input: &[Dependency]) -> Vec<StepId> {
+    let iter1 = input.iter().map(|d| d.step);
+    let iter2 = input.iter().map(|d| d.prereq);
+    iter1.chain(iter2).sorted().unique().collect()
+}
 
-    type StepId = char;
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generate;
+    use crate::generate::Rng;
+    use crate::input;
 
-    #[derive(Debug, PartialEq)]
-    struct Dependency {
-        pub step: StepId,
-        pub prereq: StepId,
+    #[test]
+    fn example() {
+        let input = sample_input();
+        assert_eq!(solve_part1(&input), "CABDFE".to_string());
     }
 
-    impl FromStr for Dependency {
-        type Err = Box<Error>;
-        fn from_str(s: &str) -> Result<Self, <Self as FromStr>::Err> {
-            lazy_static! {
-                static ref RE: Regex =
-                    Regex::new(r"^Step (.) must be finished before step (.) can begin.$").unwrap();
+    #[test]
+    fn orders_respect_every_dependency() {
+        for seed in 0..100 {
+            let dependencies = parse(&generate::day7(&mut Rng::seeded(seed), 12, 30)).unwrap();
+            let order = solve_part1(&dependencies);
+
+            assert_eq!(
+                order.chars().sorted().collect::<Vec<_>>(),
+                step_ids(&dependencies),
+                "seed {}",
+                seed
+            );
+            for dependency in &dependencies {
+                assert!(
+                    order.find(dependency.prereq) < order.find(dependency.step),
+                    "seed {}: {:?} violated by {}",
+                    seed,
+                    dependency,
+                    order
+                );
             }
-
-            let captures = RE
-                .captures(s)
-                .expect(&format!("Regex didn't match '{}'", &s));
-            Ok(Dependency {
-                prereq: captures[1].chars().next().expect("Chars for prereq"),
-                step: captures[2].chars().next().expect("Chars for step"),
-            })
         }
     }
 
-    fn step_ids(input: &[Dependency]) -> Vec<StepId> {
-        let iter1 = input.iter().map(|d| d.step);
-        let iter2 = input.iter().map(|d| d.prereq);
-        iter1.chain(iter2).sorted().unique().collect()
+    #[test]
+    fn cycles_are_errors() {
+        let input = "\
+Step C must be finished before step A can begin.
+Step A must be finished before step B can begin.
+Step B must be finished before step A can begin.";
+        let error = parse(input).unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.expected, "a dependency which isn't part of a cycle");
+
+        let error = parse("Step A must be finished before step A can begin.").unwrap_err();
+        assert_eq!(error.line, 1);
+    }
+
+    #[test]
+    fn solvers_stop_at_cycles() {
+        let dependencies = [
+            Dependency {
+                step: 'A',
+                prereq: 'C',
+            },
+            Dependency {
+                step: 'B',
+                prereq: 'A',
+            },
+            Dependency {
+                step: 'A',
+                prereq: 'B',
+            },
+        ];
+        assert_eq!(solve_part1(&dependencies), "C");
+        assert_eq!(solve_part2(&dependencies), step_duration('C'));
+    }
+
+    #[test]
+    fn test_step_duration() {
+        assert_eq!(step_duration('A'), 61);
+        assert_eq!(step_duration('B'), 62);
+        assert_eq!(step_duration('C'), 63);
+        assert_eq!(step_duration('Z'), 86);
     }
 
     #[test]
@@ -219,14 +355,34 @@ mod test {
         );
 
         assert_eq!(real_input().len(), 101);
+
+        let error =
+            parse("Step C must be finished before step A can begin.\nStep C before A").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
     }
 
     fn real_input() -> Vec<Dependency> {
-        fs::read_to_string("day7.txt")
-            .unwrap()
-            .lines()
-            .map(|s| s.parse().unwrap())
-            .collect()
+        parse(&input::load_day(7).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for line in input::load_day(7).unwrap().lines() {
+            assert_eq!(Dependency::from_str(line).unwrap().to_string(), line);
+        }
+
+        let rng = &mut Rng::seeded(23);
+        let step = |rng: &mut Rng| (b'A' + rng.below(26) as u8) as char;
+        for _ in 0..100 {
+            let dependency = Dependency {
+                step: step(rng),
+                prereq: step(rng),
+            };
+            assert_eq!(
+                Dependency::from_str(&dependency.to_string()),
+                Ok(dependency)
+            );
+        }
     }
 
     fn sample_input() -> Vec<Dependency> {
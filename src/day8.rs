@@ -1,91 +1,265 @@
-#[cfg(test)]
-mod test {
-    use itertools::Itertools;
-    use std::fs;
+use crate::error::ParseError;
+use crate::solution::Solution;
 
-    #[test]
-    fn real_data() {
-        let string = fs::read_to_string("day8.txt").unwrap();
-        let node = &parse_input(&string)[0];
+pub struct Day8;
+
+impl Solution for Day8 {
+    const DAY: u8 = 8;
 
-        assert_eq!(sum_metadata(node), 37905);
-        assert_eq!(value_of_node(node), 33891);
+    type Input = LicenseNode;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: &str) -> Result<LicenseNode, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(root: &LicenseNode) -> usize {
+        sum_metadata(root)
     }
 
+    fn part2(root: &LicenseNode) -> usize {
+        value_of_node(root)
+    }
+}
+
+/// Values each node once, children first, with an explicit stack so deep trees can't overflow
+/// the call stack
+pub fn value_of_node(l: &LicenseNode) -> usize {
+    // each node on the path down, with the values of the children done so far
+    let mut stack = vec![(l, Vec::new())];
+    loop {
+        let (node, values) = stack.last().unwrap();
+        if let Some(child) = node.children.get(values.len()) {
+            stack.push((child, Vec::new()));
+            continue;
+        }
+
+        let (node, values) = stack.pop().unwrap();
+        let value = if node.children.is_empty() {
+            node.metadata.iter().sum()
+        } else {
+            node.metadata
+                .iter()
+                .filter_map(|&index| index.checked_sub(1).and_then(|i| values.get(i)))
+                .sum()
+        };
+        match stack.last_mut() {
+            Some((_, parent_values)) => parent_values.push(value),
+            None => return value,
+        }
+    }
+}
+
+pub fn sum_metadata(l: &LicenseNode) -> usize {
+    let mut sum = 0;
+    let mut stack = vec![l];
+    while let Some(node) = stack.pop() {
+        sum += node.metadata.iter().sum::<usize>();
+        stack.extend(&node.children);
+    }
+    sum
+}
+
+/// Parses the single root node, which must consume the entire input
+pub fn parse_input(input: &str) -> Result<LicenseNode, ParseError> {
+    let line = input.trim_end();
+
+    // remember where each number started, so errors can point at it
+    let mut numbers = Vec::new();
+    let mut offsets = Vec::new();
+    for (offset, word) in words(line) {
+        let number = word
+            .parse::<usize>()
+            .map_err(|_| ParseError::new(line, offset, "a non-negative number"))?;
+        numbers.push(number);
+        offsets.push(offset);
+    }
+
+    let (root, remainder) =
+        parse_node(&numbers).ok_or_else(|| ParseError::new(line, line.len(), "more numbers"))?;
+    if !remainder.is_empty() {
+        let offset = offsets[numbers.len() - remainder.len()];
+        return Err(ParseError::new(line, offset, "end of input"));
+    }
+    Ok(root)
+}
+
+/// Each whitespace separated word, and the byte offset it starts at
+fn words(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.split(char::is_whitespace)
+        .scan(0, move |offset, word| {
+            let start = *offset;
+            *offset += word.len();
+            // step over the separator, which may be more than one byte
+            *offset += line[*offset..].chars().next().map_or(0, char::len_utf8);
+            Some((start, word))
+        })
+        .filter(|(_, word)| !word.is_empty())
+}
+
+/// Parses one node with an explicit stack, so deep trees can't overflow the call stack.
+/// Returns the remainder of the input, or `None` if it ran out part way through a node.
+fn parse_node(input: &[usize]) -> Option<(LicenseNode, &[usize])> {
+    fn header(remaining: &mut &[usize]) -> Option<(usize, usize)> {
+        let (&num_children, &num_metadata) = (remaining.first()?, remaining.get(1)?);
+        *remaining = &remaining[2..];
+        Some((num_children, num_metadata))
+    }
+
+    let mut remaining = input;
+    // each node on the path down: (children still to parse, metadata count, children so far)
+    let (num_children, num_metadata) = header(&mut remaining)?;
+    let mut stack = vec![(num_children, num_metadata, Vec::new())];
+    loop {
+        let (children_left, _, _) = stack.last_mut().unwrap();
+        if *children_left > 0 {
+            *children_left -= 1;
+            let (num_children, num_metadata) = header(&mut remaining)?;
+            stack.push((num_children, num_metadata, Vec::new()));
+            continue;
+        }
+
+        let (_, num_metadata, children) = stack.pop().unwrap();
+        let node = LicenseNode {
+            children,
+            metadata: Vec::from(remaining.get(0..num_metadata)?),
+        };
+        remaining = &remaining[num_metadata..];
+        match stack.last_mut() {
+            Some((_, _, siblings)) => siblings.push(node),
+            None => return Some((node, remaining)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LicenseNode {
+    pub children: Vec<LicenseNode>,
+    pub metadata: Vec<usize>,
+}
+
+impl LicenseNode {
+    /// The inverse of `parse_input`: header, children, then metadata
+    pub fn encode(&self) -> Vec<usize> {
+        let mut numbers = vec![self.children.len(), self.metadata.len()];
+        // each node on the path down, with the index of its next child
+        let mut stack = vec![(self, 0)];
+        while let Some((node, next)) = stack.pop() {
+            match node.children.get(next) {
+                Some(child) => {
+                    stack.push((node, next + 1));
+                    numbers.extend([child.children.len(), child.metadata.len()]);
+                    stack.push((child, 0));
+                }
+                None => numbers.extend(&node.metadata),
+            }
+        }
+        numbers
+    }
+}
+
+impl Drop for LicenseNode {
+    /// Dismantles the tree a level at a time, as dropping it recursively would overflow the
+    /// call stack for deep trees
+    fn drop(&mut self) {
+        let mut nodes = std::mem::take(&mut self.children);
+        while let Some(mut node) = nodes.pop() {
+            nodes.append(&mut node.children);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generate;
+    use crate::generate::Rng;
+    use itertools::Itertools;
+
     #[test]
     fn sample_data() {
         let input = "2 3 0 3 10 11 12 1 1 0 1 99 2 1 1 2";
-        let node = &parse_input(input)[0];
+        let node = &parse_input(input).unwrap();
 
         assert_eq!(sum_metadata(node), 138);
         assert_eq!(value_of_node(node), 66);
+        assert_eq!(
+            node.encode()
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            input
+        );
     }
 
-    fn value_of_node(l: &LicenseNode) -> usize {
-        if l.children.is_empty() {
-            return sum_metadata(l);
+    #[test]
+    fn sum_metadata_matches_re_encoded_tree() {
+        for seed in 0..100 {
+            let input = generate::day8(&mut Rng::seeded(seed), 60);
+            let root = parse_input(&input).unwrap();
+            let encoded = root.encode();
+
+            assert_eq!(encoded.iter().join(" "), input, "seed {}", seed);
+            assert_eq!(
+                sum_metadata(&root),
+                sum_encoded_metadata(&encoded),
+                "seed {}",
+                seed
+            );
         }
+    }
 
+    /// Walks the flat encoding with an explicit stack of (children left, metadata count)
+    fn sum_encoded_metadata(numbers: &[usize]) -> usize {
         let mut sum = 0;
-        for &index in &l.metadata {
-            if let Some(child_node) = l.children.get(index - 1) {
-                sum += value_of_node(&child_node);
+        let mut stack = vec![];
+        let mut numbers = numbers.iter().copied();
+        stack.push((numbers.next().unwrap(), numbers.next().unwrap()));
+
+        while let Some((children, metadata)) = stack.pop() {
+            if children > 0 {
+                stack.push((children - 1, metadata));
+                stack.push((numbers.next().unwrap(), numbers.next().unwrap()));
+            } else {
+                sum += numbers.by_ref().take(metadata).sum::<usize>();
             }
         }
 
+        assert_eq!(numbers.next(), None);
         sum
     }
 
-    fn sum_metadata(l: &LicenseNode) -> usize {
-        let own = l.metadata.iter().sum::<usize>();
-        let children = l
-            .children
-            .iter()
-            .map(|child| sum_metadata(child))
-            .sum::<usize>();
-        own + children
-    }
-
-    fn parse_input(input: &str) -> Vec<LicenseNode> {
-        let vec = input
-            .trim()
-            .split_whitespace()
-            .map(|s| s.parse::<usize>().unwrap())
-            .collect_vec();
+    #[test]
+    fn deep_trees_fit_on_the_stack() {
+        // each node has one child, which its only metadata entry refers to
+        let depth = 200_000;
+        let input = format!(
+            "{}0 1 7{}",
+            "1 1 ".repeat(depth - 1),
+            " 1".repeat(depth - 1)
+        );
+        let root = parse_input(&input).unwrap();
 
-        let (vec, remainder) = parse_n_nodes(1, &vec);
-        assert!(remainder.is_empty(), "entire input should be consumed");
-        assert_eq!(vec.len(), 1, "exactly one node should be returned");
-        vec
+        assert_eq!(sum_metadata(&root), 7 + depth - 1);
+        assert_eq!(value_of_node(&root), 7);
+        assert_eq!(root.encode().iter().join(" "), input);
     }
 
-    // return remainder of input
-    fn parse_n_nodes(expected_children: usize, input: &[usize]) -> (Vec<LicenseNode>, &[usize]) {
-        if expected_children == 0 {
-            return (Vec::new(), input);
-        }
-
-        let mut vec = Vec::new();
-        let mut remaining = input;
-
-        for _ in 0..expected_children {
-            let num_children = remaining[0];
-            let num_metadata = remaining[1];
-            let (parsed, unprocessed) = parse_n_nodes(num_children, &remaining[2..]);
-
-            vec.push(LicenseNode {
-                children: parsed,
-                metadata: Vec::from(&unprocessed[0..num_metadata]),
-            });
-            remaining = &unprocessed[num_metadata..];
-        }
+    #[test]
+    fn numbers_may_be_separated_by_any_whitespace() {
+        let root = parse_input("2 3 0 3\t10 11 12\n1 1 0 1 99 2  1 1 2\n").unwrap();
+        assert_eq!(sum_metadata(&root), 138);
 
-        (vec, remaining)
+        let error = parse_input("0\t1\n5\u{3000}7").unwrap_err();
+        assert_eq!((error.column, error.expected.as_str()), (7, "end of input"));
     }
 
-    #[derive(Debug)]
-    struct LicenseNode {
-        pub children: Vec<LicenseNode>,
-        pub metadata: Vec<usize>,
+    #[test]
+    fn malformed_trees_are_errors() {
+        assert_eq!(parse_input("0 1 5 7").unwrap_err().column, 7);
+        assert_eq!(parse_input("1 1 0 2 5").unwrap_err().column, 10);
+        assert_eq!(parse_input("0 1 x").unwrap_err().column, 5);
     }
 }
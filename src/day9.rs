@@ -1,348 +1,411 @@
-#[cfg(test)]
-mod test {
-    use core::fmt::Write;
-    use std::collections::HashMap;
-    use std::fmt::Debug;
-    use std::fmt::Error;
-    use std::fmt::Formatter;
+use core::fmt::Write;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fmt::Error;
+use std::fmt::Formatter;
 
-    use itertools::Itertools;
+use crate::error::{parse_capture, ParseError};
+use crate::solution::Solution;
+use regex::Regex;
 
-    #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
-    struct PlayerId(usize);
-
-    // concept of a turn
-    #[derive(Debug, PartialEq, Copy, Clone)]
-    struct Marble(usize);
-
-    #[derive(Clone)]
-    struct Game<T, C>
-    where
-        T: Circle<C>,
-        T: Clone,
-        C: Clone,
-    {
-        num_players: usize,
-        max_marble: usize,
-        scores: HashMap<PlayerId, usize>,
-        circle: T,
-        circle_current: C,
-        next_player: PlayerId,
-        next_marble: Marble,
-    }
-
-    trait Circle<Crsr> {
-        fn len(&self) -> usize;
-        fn get(&self, cursor: &Crsr) -> Option<&Marble>;
-        fn seek_forward(&self, cursor: &Crsr, steps: usize) -> Crsr;
-        fn seek_back(&self, cursor: &Crsr, steps: usize) -> Crsr;
-        fn insert(&mut self, cursor: &Crsr, element: Marble);
-        fn remove(&mut self, cursor: &Crsr) -> Marble;
-        fn cursor_to_index(&self, cursor: &Crsr) -> usize;
-        fn into_iter(self) -> Box<Iterator<Item = Marble>>;
-    }
-
-    fn new_game_segmented(num_players: usize, max_marble: usize) -> Game<SegmentedVec, Cursor> {
-        let mut circle = SegmentedVec::default();
-        let initial_cursor = Cursor::default();
-        circle.insert(&initial_cursor, Marble(0));
-
-        Game {
-            num_players,
-            max_marble,
-            scores: HashMap::new(),
-            circle,
-            circle_current: initial_cursor,
-            next_player: PlayerId(1),
-            next_marble: Marble(1),
-        }
+pub struct Day9;
+
+impl Solution for Day9 {
+    const DAY: u8 = 9;
+
+    /// number of players, and the value of the last marble
+    type Input = (usize, usize);
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: &str) -> Result<(usize, usize), ParseError> {
+        parse(input)
     }
 
-    fn new_game_flat(num_players: usize, max_marble: usize) -> Game<FlatVec, usize> {
-        let mut circle = FlatVec::default();
-        circle.insert(&0, Marble(0));
+    fn part1(&(num_players, max_marble): &(usize, usize)) -> usize {
+        play(num_players, max_marble)
+    }
 
-        Game {
-            num_players,
-            max_marble,
-            scores: HashMap::new(),
-            circle,
-            circle_current: 0,
-            next_player: PlayerId(1),
-            next_marble: Marble(1),
-        }
+    fn part2(&(num_players, max_marble): &(usize, usize)) -> usize {
+        play(num_players, max_marble * 100)
     }
+}
 
-    impl<T, C> Game<T, C>
-    where
-        T: Circle<C>,
-        T: Clone,
-        C: Clone,
-    {
-        fn winner(&self) -> (PlayerId, usize) {
-            let (player_id, score) = self
-                .scores
-                .iter()
-                .max_by_key(|&(_, &score)| score)
-                .expect("Must have at least one player");
-            (*player_id, *score)
-        }
+/// e.g. `458 players; last marble is worth 72019 points`
+pub fn parse(input: &str) -> Result<(usize, usize), ParseError> {
+    lazy_static! {
+        static ref RE: Regex =
+            Regex::new(r"^(?P<players>\d+) players; last marble is worth (?P<points>\d+) points$")
+                .unwrap();
+    }
+    let line = input.trim();
+    let captures = RE.captures(line).ok_or_else(|| {
+        ParseError::new(
+            line,
+            0,
+            "a game like '10 players; last marble is worth 1618 points'",
+        )
+    })?;
+
+    let players = parse_capture(line, &captures, "players", "at least one player")?;
+    if players == 0 {
+        return Err(ParseError::new(line, 0, "at least one player"));
+    }
+    let points = parse_capture(line, &captures, "points", "a number that fits in usize")?;
+    Ok((players, points))
+}
 
-        // returns true iff game should continue (i.e. there are marbles remaining)
-        fn next(&mut self) -> bool {
-            let marble = self.next_marble;
+/// Plays a full game, returning the winning score
+pub fn play(num_players: usize, max_marble: usize) -> usize {
+    let mut game = new_game_segmented(num_players, max_marble);
+    while game.take_turn() {}
+    game.winner().1
+}
 
-            if marble.0 % 23 == 0 {
-                // the current player keeps the marble they would have placed, adding it to their score
-                *self.scores.entry(self.next_player).or_default() += marble.0;
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub struct PlayerId(pub usize);
+
+// concept of a turn
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Marble(pub usize);
+
+#[derive(Clone)]
+pub struct Game<T, C>
+where
+    T: Circle<C>,
+    T: Clone,
+    C: Clone,
+{
+    num_players: usize,
+    max_marble: usize,
+    scores: HashMap<PlayerId, usize>,
+    circle: T,
+    circle_current: C,
+    next_player: PlayerId,
+    next_marble: Marble,
+}
 
-                // the marble 7 marbles counter-clockwise from the current marble is removed from the circle
-                let index = self.index_backwards_7();
-                let removed_marble = self.circle.remove(&index);
+pub trait Circle<Crsr> {
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn get(&self, cursor: &Crsr) -> Option<&Marble>;
+    fn seek_forward(&self, cursor: &Crsr, steps: usize) -> Crsr;
+    fn seek_back(&self, cursor: &Crsr, steps: usize) -> Crsr;
+    fn insert(&mut self, cursor: &Crsr, element: Marble);
+    fn remove(&mut self, cursor: &Crsr) -> Marble;
+    fn cursor_to_index(&self, cursor: &Crsr) -> usize;
+    fn into_iter(self) -> Box<dyn Iterator<Item = Marble>>;
+}
 
-                // and also added to the current player's score
-                *self.scores.entry(self.next_player).or_default() += removed_marble.0;
+pub fn new_game_segmented(num_players: usize, max_marble: usize) -> Game<SegmentedVec, Cursor> {
+    let mut circle = SegmentedVec::default();
+    let initial_cursor = Cursor::default();
+    circle.insert(&initial_cursor, Marble(0));
+
+    Game {
+        num_players,
+        max_marble,
+        scores: HashMap::new(),
+        circle,
+        circle_current: initial_cursor,
+        next_player: PlayerId(1),
+        next_marble: Marble(1),
+    }
+}
 
-                // The marble located immediately clockwise of the marble that was removed becomes the new current marble
-                self.circle_current = index;
+pub fn new_game_flat(num_players: usize, max_marble: usize) -> Game<FlatVec, usize> {
+    let mut circle = FlatVec::default();
+    circle.insert(&0, Marble(0));
+
+    Game {
+        num_players,
+        max_marble,
+        scores: HashMap::new(),
+        circle,
+        circle_current: 0,
+        next_player: PlayerId(1),
+        next_marble: Marble(1),
+    }
+}
 
-                return self.proceed_turn();
-            }
+impl<T, C> Game<T, C>
+where
+    T: Circle<C>,
+    T: Clone,
+    C: Clone,
+{
+    /// The highest scorer; before any marble scores, everyone is tied on 0 and player 1 wins
+    pub fn winner(&self) -> (PlayerId, usize) {
+        self.scores
+            .iter()
+            .max_by_key(|&(_, &score)| score)
+            .map(|(&player_id, &score)| (player_id, score))
+            .unwrap_or((PlayerId(1), 0))
+    }
 
-            let index = self.index_forwards_2();
-            self.circle.insert(&index, marble);
-            self.circle_current = index;
+    // returns true iff game should continue (i.e. there are marbles remaining)
+    pub fn take_turn(&mut self) -> bool {
+        let marble = self.next_marble;
 
-            self.proceed_turn()
-        }
+        if marble.0.is_multiple_of(23) {
+            // the current player keeps the marble they would have placed, adding it to their score
+            *self.scores.entry(self.next_player).or_default() += marble.0;
 
-        fn proceed_turn(&mut self) -> bool {
-            if self.next_marble.0 > self.max_marble {
-                return false; // game ends
-            }
+            // the marble 7 marbles counter-clockwise from the current marble is removed from the circle
+            let index = self.index_backwards_7();
+            let removed_marble = self.circle.remove(&index);
 
-            self.next_marble = Marble(self.next_marble.0 + 1);
-            self.next_player = PlayerId(self.next_player.0 % self.num_players + 1);
-            true
-        }
+            // and also added to the current player's score
+            *self.scores.entry(self.next_player).or_default() += removed_marble.0;
 
-        fn index_backwards_7(&self) -> C {
-            self.circle.seek_back(&self.circle_current, 7)
-        }
+            // The marble located immediately clockwise of the marble that was removed becomes the new current marble
+            self.circle_current = index;
 
-        fn index_forwards_2(&self) -> C {
-            self.circle.seek_forward(&self.circle_current, 2)
+            return self.proceed_turn();
         }
-    }
 
-    impl<T, C> Debug for Game<T, C>
-    where
-        T: Circle<C>,
-        T: Clone,
-        C: Clone,
-    {
-        fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-            f.write_char('[')?;
-            self.next_player.0.fmt(f)?;
-            f.write_char(']')?;
-
-            let current_index = self.circle.cursor_to_index(&self.circle_current);
-
-            for (index, marble) in self.circle.clone().into_iter().enumerate() {
-                if index == current_index {
-                    f.write_char('(')?;
-                    marble.0.fmt(f)?;
-                    f.write_char(')')?;
-                } else {
-                    f.write_char(' ')?;
-                    marble.0.fmt(f)?;
-                    f.write_char(' ')?;
-                }
-            }
+        let index = self.index_forwards_2();
+        self.circle.insert(&index, marble);
+        self.circle_current = index;
 
-            Ok(())
+        self.proceed_turn()
+    }
+
+    fn proceed_turn(&mut self) -> bool {
+        if self.next_marble.0 > self.max_marble {
+            return false; // game ends
         }
+
+        self.next_marble = Marble(self.next_marble.0 + 1);
+        self.next_player = PlayerId(self.next_player.0 % self.num_players + 1);
+        true
     }
 
-    #[derive(Default, Clone)]
-    struct SegmentedVec {
-        segments: Vec<Vec<Marble>>,
+    fn index_backwards_7(&self) -> C {
+        self.circle.seek_back(&self.circle_current, 7)
     }
 
-    /// Points to a slot in the SegmentedVec, but there might not be an element there
-    #[derive(Debug, PartialEq, Default, Clone)]
-    struct Cursor {
-        segment: usize,
-        offset: usize,
+    fn index_forwards_2(&self) -> C {
+        self.circle.seek_forward(&self.circle_current, 2)
     }
+}
 
-    impl SegmentedVec {
-        fn repartition(&mut self, index: usize) {
-            let max = 1024;
-            let segment = self.segments.get_mut(index).unwrap();
-            if segment.len() > max {
-                let split = segment.split_off(max / 2);
-                self.segments.insert(index + 1, split);
+impl<T, C> Debug for Game<T, C>
+where
+    T: Circle<C>,
+    T: Clone,
+    C: Clone,
+{
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.write_char('[')?;
+        self.next_player.0.fmt(f)?;
+        f.write_char(']')?;
+
+        let current_index = self.circle.cursor_to_index(&self.circle_current);
+
+        for (index, marble) in self.circle.clone().into_iter().enumerate() {
+            if index == current_index {
+                f.write_char('(')?;
+                marble.0.fmt(f)?;
+                f.write_char(')')?;
+            } else {
+                f.write_char(' ')?;
+                marble.0.fmt(f)?;
+                f.write_char(' ')?;
             }
         }
+
+        Ok(())
     }
+}
 
-    impl Circle<Cursor> for SegmentedVec {
-        fn len(&self) -> usize {
-            self.segments.iter().map(|segment| segment.len()).sum()
-        }
+#[derive(Default, Clone)]
+pub struct SegmentedVec {
+    segments: Vec<Vec<Marble>>,
+}
+
+/// Points to a slot in the SegmentedVec, but there might not be an element there
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct Cursor {
+    segment: usize,
+    offset: usize,
+}
 
-        fn get(&self, cursor: &Cursor) -> Option<&Marble> {
-            let segment = self.segments.get(cursor.segment)?;
-            segment.get(cursor.offset)
+impl SegmentedVec {
+    fn repartition(&mut self, index: usize) {
+        let max = 1024;
+        let segment = self.segments.get_mut(index).unwrap();
+        if segment.len() > max {
+            let split = segment.split_off(max / 2);
+            self.segments.insert(index + 1, split);
         }
+    }
+}
 
-        /// wraps around as necessary
-        fn seek_forward(&self, cursor: &Cursor, steps: usize) -> Cursor {
-            let current_segment = &self.segments[cursor.segment];
+impl Circle<Cursor> for SegmentedVec {
+    fn len(&self) -> usize {
+        self.segments.iter().map(|segment| segment.len()).sum()
+    }
 
-            // simplest case first - the desired offset is still within the current segment
-            if cursor.offset + steps < current_segment.len() {
-                return Cursor {
-                    segment: cursor.segment,
-                    offset: cursor.offset + steps,
-                };
-            }
+    fn get(&self, cursor: &Cursor) -> Option<&Marble> {
+        let segment = self.segments.get(cursor.segment)?;
+        segment.get(cursor.offset)
+    }
 
-            // otherwise, we need to move to a new segment
-            let next_segment = (cursor.segment + 1) % self.segments.len();
-            let remaining_steps =
-                steps as isize - (current_segment.len() as isize - cursor.offset as isize);
-            let intermediate_cursor = Cursor {
-                segment: next_segment,
-                offset: 0,
-            };
+    /// wraps around as necessary
+    fn seek_forward(&self, cursor: &Cursor, steps: usize) -> Cursor {
+        let current_segment = &self.segments[cursor.segment];
 
-            self.seek_forward(&intermediate_cursor, remaining_steps as usize)
+        // simplest case first - the desired offset is still within the current segment
+        if cursor.offset + steps < current_segment.len() {
+            return Cursor {
+                segment: cursor.segment,
+                offset: cursor.offset + steps,
+            };
         }
 
-        fn seek_back(&self, cursor: &Cursor, steps: usize) -> Cursor {
-            // simplest case first - the desired offset is still within the current segment
-            if (cursor.offset as isize) - (steps as isize) >= 0 {
-                return Cursor {
-                    segment: cursor.segment,
-                    offset: cursor.offset - steps,
-                };
-            }
+        // otherwise, we need to move to a new segment
+        let next_segment = (cursor.segment + 1) % self.segments.len();
+        let remaining_steps =
+            steps as isize - (current_segment.len() as isize - cursor.offset as isize);
+        let intermediate_cursor = Cursor {
+            segment: next_segment,
+            offset: 0,
+        };
 
-            let next_segment = (cursor.segment as isize + self.segments.len() as isize - 1)
-                as usize
-                % self.segments.len();
-            let remaining_steps = steps - cursor.offset;
-            let intermediate_cursor = Cursor {
-                segment: next_segment,
-                offset: self.segments[next_segment].len(),
-            };
+        self.seek_forward(&intermediate_cursor, remaining_steps as usize)
+    }
 
-            self.seek_back(&intermediate_cursor, remaining_steps)
+    fn seek_back(&self, cursor: &Cursor, steps: usize) -> Cursor {
+        // simplest case first - the desired offset is still within the current segment
+        if (cursor.offset as isize) - (steps as isize) >= 0 {
+            return Cursor {
+                segment: cursor.segment,
+                offset: cursor.offset - steps,
+            };
         }
 
-        fn insert(&mut self, cursor: &Cursor, element: Marble) {
-            match self.segments.get_mut(cursor.segment) {
-                Some(ref mut vec) => {
-                    vec.insert(cursor.offset, element);
-                    self.repartition(cursor.segment);
-                }
-                None => {
-                    self.segments.push(vec![element]);
-                }
-            }
-        }
+        let next_segment = (cursor.segment as isize + self.segments.len() as isize - 1) as usize
+            % self.segments.len();
+        let remaining_steps = steps - cursor.offset;
+        let intermediate_cursor = Cursor {
+            segment: next_segment,
+            offset: self.segments[next_segment].len(),
+        };
 
-        fn remove(&mut self, cursor: &Cursor) -> Marble {
-            let segment = self.segments.get_mut(cursor.segment).unwrap();
+        self.seek_back(&intermediate_cursor, remaining_steps)
+    }
 
-            if cursor.offset >= segment.len() {
-                // TODO(dfox): ideally invalid cursors could never exist...
-                let overshot = cursor.offset as isize - segment.len() as isize;
-                return self.remove(&Cursor {
-                    segment: cursor.segment + 1,
-                    offset: overshot as usize,
-                });
+    fn insert(&mut self, cursor: &Cursor, element: Marble) {
+        match self.segments.get_mut(cursor.segment) {
+            Some(ref mut vec) => {
+                vec.insert(cursor.offset, element);
+                self.repartition(cursor.segment);
+            }
+            None => {
+                self.segments.push(vec![element]);
             }
-
-            segment.remove(cursor.offset)
         }
+    }
+
+    fn remove(&mut self, cursor: &Cursor) -> Marble {
+        let segment = self.segments.get_mut(cursor.segment).unwrap();
 
-        fn cursor_to_index(&self, cursor: &Cursor) -> usize {
-            self.segments
-                .iter()
-                .take(cursor.segment)
-                .map(|vec| vec.len())
-                .sum::<usize>()
-                + cursor.offset
+        if cursor.offset >= segment.len() {
+            // TODO(dfox): ideally invalid cursors could never exist...
+            let overshot = cursor.offset as isize - segment.len() as isize;
+            return self.remove(&Cursor {
+                segment: cursor.segment + 1,
+                offset: overshot as usize,
+            });
         }
 
-        fn into_iter(self) -> Box<Iterator<Item = Marble>> {
-            struct SegmentedVecIter {
-                vec: SegmentedVec,
-                cursor: Cursor,
-            }
+        segment.remove(cursor.offset)
+    }
 
-            impl Iterator for SegmentedVecIter {
-                type Item = Marble;
+    fn cursor_to_index(&self, cursor: &Cursor) -> usize {
+        self.segments
+            .iter()
+            .take(cursor.segment)
+            .map(|vec| vec.len())
+            .sum::<usize>()
+            + cursor.offset
+    }
 
-                fn next(&mut self) -> Option<Self::Item> {
-                    let item = self.vec.get(&self.cursor).map(|&m| m);
-                    self.cursor = self.vec.seek_forward(&self.cursor, 1);
+    fn into_iter(self) -> Box<dyn Iterator<Item = Marble>> {
+        struct SegmentedVecIter {
+            vec: SegmentedVec,
+            cursor: Cursor,
+        }
 
-                    item
-                }
-            }
+        impl Iterator for SegmentedVecIter {
+            type Item = Marble;
 
-            let len = self.len();
-            let iter = SegmentedVecIter {
-                vec: self,
-                cursor: Cursor::default(),
-            };
-            Box::new(iter.take(len))
+            fn next(&mut self) -> Option<Self::Item> {
+                let item = self.vec.get(&self.cursor).copied();
+                self.cursor = self.vec.seek_forward(&self.cursor, 1);
+
+                item
+            }
         }
-    }
 
-    #[derive(Default, Clone)]
-    struct FlatVec {
-        marbles: Vec<Marble>,
+        let len = self.len();
+        let iter = SegmentedVecIter {
+            vec: self,
+            cursor: Cursor::default(),
+        };
+        Box::new(iter.take(len))
     }
+}
 
-    impl Circle<usize> for FlatVec {
-        fn len(&self) -> usize {
-            self.marbles.len()
-        }
+#[derive(Default, Clone)]
+pub struct FlatVec {
+    marbles: Vec<Marble>,
+}
 
-        fn get(&self, &cursor: &usize) -> Option<&Marble> {
-            self.marbles.get(cursor)
-        }
+impl Circle<usize> for FlatVec {
+    fn len(&self) -> usize {
+        self.marbles.len()
+    }
 
-        fn seek_forward(&self, &cursor: &usize, steps: usize) -> usize {
-            (cursor + steps) % self.marbles.len()
-        }
+    fn get(&self, &cursor: &usize) -> Option<&Marble> {
+        self.marbles.get(cursor)
+    }
 
-        fn seek_back(&self, &cursor: &usize, steps: usize) -> usize {
-            ((cursor as isize) - (steps as isize) + (self.marbles.len() as isize)) as usize
-                % self.marbles.len()
-        }
+    fn seek_forward(&self, &cursor: &usize, steps: usize) -> usize {
+        (cursor + steps) % self.marbles.len()
+    }
 
-        fn insert(&mut self, &cursor: &usize, element: Marble) {
-            self.marbles.insert(cursor, element)
-        }
+    fn seek_back(&self, &cursor: &usize, steps: usize) -> usize {
+        ((cursor as isize) - (steps as isize) + (self.marbles.len() as isize)) as usize
+            % self.marbles.len()
+    }
 
-        fn remove(&mut self, &cursor: &usize) -> Marble {
-            self.marbles.remove(cursor)
-        }
+    fn insert(&mut self, &cursor: &usize, element: Marble) {
+        self.marbles.insert(cursor, element)
+    }
 
-        fn cursor_to_index(&self, cursor: &usize) -> usize {
-            *cursor
-        }
+    fn remove(&mut self, &cursor: &usize) -> Marble {
+        self.marbles.remove(cursor)
+    }
 
-        fn into_iter(self) -> Box<Iterator<Item = Marble>> {
-            Box::new(self.marbles.into_iter())
-        }
+    fn cursor_to_index(&self, cursor: &usize) -> usize {
+        *cursor
+    }
+
+    fn into_iter(self) -> Box<dyn Iterator<Item = Marble>> {
+        Box::new(self.marbles.into_iter())
     }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generate;
+    use crate::generate::Rng;
+    use itertools::Itertools;
 
     #[test]
     fn cursor_seek_wraps_nicely() {
@@ -452,12 +515,21 @@ mod test {
     #[test]
     fn sample_data_1() {
         let mut game = new_game_segmented(9, 25);
-        while game.next() {
+        while game.take_turn() {
             dbg!(&game);
         }
         assert_eq!(game.winner(), (PlayerId(5), 32));
     }
 
+    #[test]
+    fn no_scores_before_marble_23() {
+        assert_eq!(play(10, 5), 0);
+        assert_eq!(
+            Day9::part1(&parse("10 players; last marble is worth 21 points").unwrap()),
+            0
+        );
+    }
+
     #[test]
     fn comparative() {
         let marbles = 45;
@@ -466,66 +538,85 @@ mod test {
 
         let rounds = 20;
         for _ in 0..rounds {
-            segmented.next();
+            segmented.take_turn();
             dbg!(&segmented);
         }
         for _ in 0..rounds {
-            flat.next();
+            flat.take_turn();
             dbg!(&flat);
         }
 
-        while segmented.next() {}
-        while flat.next() {}
+        while segmented.take_turn() {}
+        while flat.take_turn() {}
 
         assert_eq!(segmented.winner().1, flat.winner().1);
     }
 
+    #[test]
+    fn every_circle_yields_identical_winners() {
+        for seed in 0..30 {
+            let rng = &mut Rng::seeded(seed);
+            // some games end before anyone scores
+            let last_marble = rng.below(if seed % 3 == 0 { 30 } else { 2000 });
+            let (players, marbles) = parse(&generate::day9(rng, 50, last_marble)).unwrap();
+
+            let mut segmented = new_game_segmented(players, marbles);
+            let mut flat = new_game_flat(players, marbles);
+            while segmented.take_turn() {}
+            while flat.take_turn() {}
+
+            assert_eq!(segmented.scores, flat.scores, "seed {}", seed);
+            assert_eq!(segmented.winner().1, flat.winner().1, "seed {}", seed);
+        }
+    }
+
     #[test]
     fn sample_data_2() {
         let mut game = new_game_segmented(10, 1618);
-        while game.next() {}
+        while game.take_turn() {}
         assert_eq!(game.winner().1, 8317);
     }
 
     #[test]
     fn sample_data_3() {
         let mut game = new_game_segmented(13, 7999);
-        while game.next() {}
+        while game.take_turn() {}
         assert_eq!(game.winner().1, 146373);
     }
 
     #[test]
     fn sample_data_4() {
         let mut game = new_game_segmented(17, 1104);
-        while game.next() {}
+        while game.take_turn() {}
         assert_eq!(game.winner().1, 2764);
     }
 
     #[test]
     fn sample_data_5() {
         let mut game = new_game_segmented(21, 6111);
-        while game.next() {}
+        while game.take_turn() {}
         assert_eq!(game.winner().1, 54718);
     }
 
     #[test]
     fn sample_data_6() {
         let mut game = new_game_segmented(30, 5807);
-        while game.next() {}
+        while game.take_turn() {}
         assert_eq!(game.winner().1, 37305);
     }
 
     #[test]
-    fn part1() {
-        let mut game = new_game_segmented(458, 72019);
-        while game.next() {}
-        assert_eq!(game.winner().1, 404502);
-    }
-
-    #[test]
-    fn part2() {
-        let mut game = new_game_segmented(458, 72019 * 100);
-        while game.next() {}
-        assert_eq!(game.winner().1, 3243916887); // this took 0.85 seconds with --release
+    fn parses_game_header() {
+        assert_eq!(
+            parse("10 players; last marble is worth 1618 points\n"),
+            Ok((10, 1618))
+        );
+        assert_eq!(parse("10 players").unwrap_err().column, 1);
+        assert_eq!(
+            parse("0 players; last marble is worth 1618 points")
+                .unwrap_err()
+                .expected,
+            "at least one player"
+        );
     }
 }
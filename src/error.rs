@@ -0,0 +1,139 @@
+use regex::Captures;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A line of puzzle input that didn't match the expected format
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// where the input came from, if known
+    pub file: Option<String>,
+    /// 1-based, or 0 if the parser only saw a single line
+    pub line: usize,
+    /// 1-based, counted in characters
+    pub column: usize,
+    /// the offending line
+    pub text: String,
+    pub expected: String,
+}
+
+impl ParseError {
+    /// `offset` is the byte offset into `text` where things went wrong
+    pub fn new(text: &str, offset: usize, expected: impl Into<String>) -> ParseError {
+        ParseError {
+            file: None,
+            line: 0,
+            column: text[..offset].chars().count() + 1,
+            text: text.to_string(),
+            expected: expected.into(),
+        }
+    }
+
+    pub fn at_line(mut self, line: usize) -> ParseError {
+        self.line = line;
+        self
+    }
+
+    pub fn in_file(mut self, file: impl Display) -> ParseError {
+        self.file = Some(file.to_string());
+        self
+    }
+
+    /// Re-anchors an error from parsing a substring, which started at byte `offset` of `line`
+    pub fn within(mut self, line: &str, offset: usize) -> ParseError {
+        self.column += line[..offset].chars().count();
+        self.text = line.to_string();
+        self
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
+        if self.line > 0 {
+            write!(f, "{}:", self.line)?;
+        }
+        write!(
+            f,
+            "{}: expected {}, found '{}'",
+            self.column, self.expected, self.text
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// Parses every line of `input` with `T::from_str`, filling in the line number of any error
+pub fn parse_lines<T>(input: &str) -> Result<Vec<T>, ParseError>
+where
+    T: FromStr<Err = ParseError>,
+{
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| line.parse().map_err(|e: ParseError| e.at_line(index + 1)))
+        .collect()
+}
+
+/// Parses a named regex capture, reporting the capture's position if it doesn't fit in `T`
+pub fn parse_capture<T: FromStr>(
+    line: &str,
+    captures: &Captures,
+    name: &str,
+    expected: &str,
+) -> Result<T, ParseError> {
+    let capture = captures
+        .name(name)
+        .ok_or_else(|| ParseError::new(line, 0, expected))?;
+    capture
+        .as_str()
+        .parse()
+        .map_err(|_| ParseError::new(line, capture.start(), expected))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display_includes_every_location_detail() {
+        let error = ParseError::new("#1 @ 3,x: 5x4", 8, "a number")
+            .at_line(12)
+            .in_file("day3.txt");
+        assert_eq!(
+            error.to_string(),
+            "day3.txt:12:9: expected a number, found '#1 @ 3,x: 5x4'"
+        );
+    }
+
+    #[test]
+    fn parse_lines_reports_the_failing_line() {
+        let error = parse_lines::<Number>("1\n2\nthree\n4").unwrap_err();
+        assert_eq!(error.line, 3);
+        assert_eq!(error.column, 1);
+        assert_eq!(error.text, "three");
+    }
+
+    #[test]
+    fn within_shifts_the_column() {
+        let error = ParseError::new("wakes", 2, "an event").within("[00:05] wakes", 8);
+        assert_eq!(error.column, 11);
+        assert_eq!(error.text, "[00:05] wakes");
+    }
+
+    #[derive(Debug)]
+    struct Number;
+
+    impl FromStr for Number {
+        type Err = ParseError;
+
+        fn from_str(s: &str) -> Result<Self, ParseError> {
+            s.parse::<u32>()
+                .map(|_| Number)
+                .map_err(|_| ParseError::new(s, 0, "a number"))
+        }
+    }
+}
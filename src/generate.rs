@@ -0,0 +1,325 @@
+//! Seeded random puzzle inputs, in exactly the formats the day parsers accept.
+//!
+//! The same seed always produces the same input, so a failing case can be replayed.
+
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+use chrono::{Duration, NaiveDate};
+use itertools::Itertools;
+
+use crate::day10::PointMeasurement;
+use crate::day3::Claim;
+use crate::day4::{Event, LogEntry};
+use crate::day7::Dependency;
+use crate::day8::LicenseNode;
+
+/// SplitMix64, which is tiny and good enough for test data
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn seeded(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n`, which must not be empty
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "empty range");
+        ((u128::from(self.next_u64()) * n as u128) >> 64) as usize
+    }
+
+    pub fn range(&mut self, range: RangeInclusive<i64>) -> i64 {
+        let (start, end) = range.into_inner();
+        assert!(start <= end, "empty range");
+        let width = (end as i128 - start as i128 + 1) as u128;
+        (start as i128 + ((u128::from(self.next_u64()) * width) >> 64) as i128) as i64
+    }
+
+    /// True `numerator` times in `denominator`
+    pub fn chance(&mut self, numerator: usize, denominator: usize) -> bool {
+        self.below(denominator) < numerator
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// An input for any day, where `size` is the number of lines, units or nodes as appropriate
+pub fn input(day: u8, seed: u64, size: usize) -> Option<String> {
+    let rng = &mut Rng::seeded(seed);
+    let input = match day {
+        1 => day1(rng, size),
+        2 => day2(rng, size, 26),
+        3 => day3(rng, size, 1000),
+        4 => day4(rng, size, size / 10 + 1),
+        5 => day5(rng, size),
+        6 => day6(rng, size, 400),
+        7 => day7(rng, 26, size),
+        8 => day8(rng, size),
+        9 => day9(rng, 500, size),
+        10 => day10(rng, size),
+        _ => return None,
+    };
+    Some(input)
+}
+
+/// `count` frequency changes like `+7` or `-3`
+pub fn day1(rng: &mut Rng, count: usize) -> String {
+    lines((0..count).map(|_| format!("{:+}", rng.range(-100..=100))))
+}
+
+/// `count` lowercase box IDs of length `len`, two of which differ by exactly one letter
+pub fn day2(rng: &mut Rng, count: usize, len: usize) -> String {
+    let letter = |rng: &mut Rng| (b'a' + rng.below(26) as u8) as char;
+    let mut ids: Vec<Vec<char>> = (0..count)
+        .map(|_| (0..len).map(|_| letter(rng)).collect())
+        .collect();
+
+    if count >= 2 && len >= 1 {
+        let original = ids[rng.below(count)].clone();
+        let mut near_duplicate = original.clone();
+        let position = rng.below(len);
+        while near_duplicate[position] == original[position] {
+            near_duplicate[position] = letter(rng);
+        }
+        let index = rng.below(count);
+        ids[index] = near_duplicate;
+        if ids.iter().all(|id| *id != original) {
+            ids[(index + 1) % count] = original;
+        }
+    }
+
+    lines(ids.iter().map(|id| id.iter().collect::<String>()))
+}
+
+/// `count` claims like `#1 @ 3,2: 5x4`, each inside a `fabric`-inch square
+pub fn day3(rng: &mut Rng, count: usize, fabric: usize) -> String {
+    let max_side = fabric.clamp(1, 30);
+    lines((1..=count).map(|id| {
+        let width = 1 + rng.below(max_side);
+        let height = 1 + rng.below(max_side);
+        Claim {
+            id,
+            offset_left: rng.below(fabric.max(width) - width + 1),
+            offset_top: rng.below(fabric.max(height) - height + 1),
+            width,
+            height,
+        }
+        .to_string()
+    }))
+}
+
+/// `shifts` nightly shifts shared between `guards` guards, in shuffled order like the real log
+pub fn day4(rng: &mut Rng, shifts: usize, guards: usize) -> String {
+    let ids: Vec<u32> = (0..guards.max(1))
+        .map(|_| rng.range(1..=3500) as u32)
+        .collect();
+    let mut entries = Vec::new();
+    let mut midnight = NaiveDate::from_ymd_opt(1518, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+
+    for _ in 0..shifts {
+        let start = rng.range(-15..=5);
+        let guard = rng.choose(&ids);
+        entries.push(LogEntry {
+            datetime: midnight + Duration::minutes(start),
+            event: Event::GuardBeginsShift(*guard),
+        });
+
+        // naps alternate falling asleep and waking, so need an even number of distinct minutes
+        // after the shift starts
+        let naps = rng.below(4);
+        let mut minutes: Vec<i64> = ((start + 1).max(0)..60).collect();
+        rng.shuffle(&mut minutes);
+        let mut minutes = minutes[..naps * 2].to_vec();
+        minutes.sort();
+        for (i, minute) in minutes.into_iter().enumerate() {
+            entries.push(LogEntry {
+                datetime: midnight + Duration::minutes(minute),
+                event: if i % 2 == 0 {
+                    Event::FallsAsleep
+                } else {
+                    Event::WakesUp
+                },
+            });
+        }
+
+        midnight += Duration::days(1);
+    }
+
+    rng.shuffle(&mut entries);
+    lines(entries.iter().map(LogEntry::to_string))
+}
+
+/// A polymer of `len` units, with plenty of adjacent pairs that react
+pub fn day5(rng: &mut Rng, len: usize) -> String {
+    let mut polymer: Vec<u8> = Vec::with_capacity(len);
+    while polymer.len() < len {
+        let unit = match polymer.last() {
+            Some(&last) if rng.chance(1, 3) => last ^ 0x20,
+            _ => {
+                let letter = b'a' + rng.below(26) as u8;
+                if rng.chance(1, 2) {
+                    letter.to_ascii_uppercase()
+                } else {
+                    letter
+                }
+            }
+        };
+        polymer.push(unit);
+    }
+    String::from_utf8(polymer).unwrap()
+}
+
+/// `count` distinct coordinates like `81, 252`, each below `extent`
+pub fn day6(rng: &mut Rng, count: usize, extent: u32) -> String {
+    assert!(
+        count as u64 <= u64::from(extent) * u64::from(extent),
+        "not enough room for {} distinct points",
+        count
+    );
+    let mut seen = HashSet::new();
+    let mut points = Vec::new();
+    while points.len() < count {
+        let point = (rng.below(extent as usize), rng.below(extent as usize));
+        if seen.insert(point) {
+            points.push(point);
+        }
+    }
+    lines(points.iter().map(|(x, y)| format!("{}, {}", x, y)))
+}
+
+/// `dependencies` distinct, acyclic constraints between the first `steps` letters
+pub fn day7(rng: &mut Rng, steps: usize, dependencies: usize) -> String {
+    let steps = steps.clamp(1, 26);
+    let mut order: Vec<char> = (b'A'..b'A' + steps as u8).map(char::from).collect();
+    rng.shuffle(&mut order);
+
+    // anything earlier in `order` can be a prerequisite of anything later
+    let mut edges: Vec<(char, char)> = order.iter().copied().tuple_combinations().collect();
+    rng.shuffle(&mut edges);
+    edges.truncate(dependencies);
+
+    lines(
+        edges
+            .iter()
+            .map(|&(prereq, step)| Dependency { step, prereq }.to_string()),
+    )
+}
+
+/// A license tree of `nodes` nodes (at least one), encoded on a single line
+pub fn day8(rng: &mut Rng, nodes: usize) -> String {
+    license_node(rng, nodes.max(1)).encode().iter().join(" ")
+}
+
+fn license_node(rng: &mut Rng, nodes: usize) -> LicenseNode {
+    // share the descendants out between 1-5 children, each of which needs at least one node
+    let descendants = nodes - 1;
+    let mut sizes = vec![
+        1;
+        if descendants == 0 {
+            0
+        } else {
+            1 + rng.below(descendants.min(5))
+        }
+    ];
+    for _ in sizes.len()..descendants {
+        let child = rng.below(sizes.len());
+        sizes[child] += 1;
+    }
+
+    LicenseNode {
+        children: sizes.iter().map(|&size| license_node(rng, size)).collect(),
+        metadata: (0..1 + rng.below(5)).map(|_| 1 + rng.below(9)).collect(),
+    }
+}
+
+/// A game header with up to `max_players` players and a last marble of `last_marble`
+pub fn day9(rng: &mut Rng, max_players: usize, last_marble: usize) -> String {
+    format!(
+        "{} players; last marble is worth {} points\n",
+        1 + rng.below(max_players.max(1)),
+        last_marble
+    )
+}
+
+/// `count` moving points, which converge into a small box some time in the future
+pub fn day10(rng: &mut Rng, count: usize) -> String {
+    let time = rng.range(10..=10_000);
+    lines((0..count).map(|_| {
+        let (x, y) = (rng.range(0..=61), rng.range(0..=9));
+        let (dx, dy) = (rng.range(-5..=5), rng.range(-5..=5));
+        let measurement = PointMeasurement {
+            point: ((x - dx * time) as isize, (y - dy * time) as isize),
+            velocity: (dx as isize, dy as isize),
+        };
+        measurement.to_padded(6)
+    }))
+}
+
+fn lines(lines: impl Iterator<Item = String>) -> String {
+    lines.map(|line| line + "\n").collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solution::DAYS;
+
+    #[test]
+    fn same_seed_same_input() {
+        for day in 1..=10 {
+            assert_eq!(input(day, 7, 30), input(day, 7, 30));
+        }
+        assert_ne!(input(1, 7, 30), input(1, 8, 30));
+        assert_eq!(input(11, 7, 30), None);
+    }
+
+    #[test]
+    fn every_day_parses_and_solves_generated_input() {
+        for solver in DAYS.iter() {
+            for seed in 0..5 {
+                let input = input(solver.day(), seed, 40).unwrap();
+                let parsed = solver
+                    .parse(&input)
+                    .unwrap_or_else(|e| panic!("day {} seed {}: {}", solver.day(), seed, e));
+                parsed.part1();
+                parsed.part2();
+            }
+        }
+    }
+
+    #[test]
+    fn rng_ranges_are_inclusive() {
+        let mut rng = Rng::seeded(1);
+        let values: HashSet<i64> = (0..1000).map(|_| rng.range(-2..=2)).collect();
+        assert_eq!(values, (-2..=2).collect());
+    }
+
+    #[test]
+    fn box_ids_include_a_near_duplicate() {
+        let ids = day2(&mut Rng::seeded(3), 50, 12);
+        assert_eq!(
+            crate::day2::find_strings_differing_by_1(ids.lines().collect()).len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Directory holding `day1.txt`..`day10.txt`, overriding the crate root
+pub const INPUTS_DIR_VAR: &str = "ADVENT2018_INPUTS";
+
+/// Where a puzzle input should be read from
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputSource {
+    /// `dayN.txt` inside the inputs directory
+    Day(u8),
+    Path(PathBuf),
+    Stdin,
+}
+
+impl InputSource {
+    /// `-` means stdin, anything else is a path
+    pub fn from_arg(arg: &str) -> InputSource {
+        if arg == "-" {
+            InputSource::Stdin
+        } else {
+            InputSource::Path(PathBuf::from(arg))
+        }
+    }
+
+    pub fn load(&self) -> Result<String, InputError> {
+        match self {
+            InputSource::Day(day) => read_file(&day_path(*day)),
+            InputSource::Path(path) => read_file(path),
+            InputSource::Stdin => {
+                let mut string = String::new();
+                io::stdin()
+                    .read_to_string(&mut string)
+                    .map_err(InputError::Stdin)?;
+                Ok(string)
+            }
+        }
+    }
+}
+
+impl Display for InputSource {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            InputSource::Day(day) => day_path(*day).display().fmt(f),
+            InputSource::Path(path) => path.display().fmt(f),
+            InputSource::Stdin => f.write_str("<stdin>"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum InputError {
+    Missing(PathBuf),
+    Io(PathBuf, io::Error),
+    Stdin(io::Error),
+}
+
+impl Display for InputError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            InputError::Missing(path) => write!(
+                f,
+                "input file '{}' does not exist (set {} to the directory holding your inputs, or pass --input)",
+                path.display(),
+                INPUTS_DIR_VAR
+            ),
+            InputError::Io(path, e) => write!(f, "couldn't read '{}': {}", path.display(), e),
+            InputError::Stdin(e) => write!(f, "couldn't read stdin: {}", e),
+        }
+    }
+}
+
+impl Error for InputError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InputError::Missing(_) => None,
+            InputError::Io(_, e) | InputError::Stdin(e) => Some(e),
+        }
+    }
+}
+
+/// Defaults to the crate root, so tests and the CLI work from any working directory
+pub fn inputs_dir() -> PathBuf {
+    match env::var_os(INPUTS_DIR_VAR) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR")),
+    }
+}
+
+pub fn day_path(day: u8) -> PathBuf {
+    inputs_dir().join(format!("day{}.txt", day))
+}
+
+/// Shorthand for `InputSource::Day(day).load()`
+pub fn load_day(day: u8) -> Result<String, InputError> {
+    InputSource::Day(day).load()
+}
+
+fn read_file(path: &Path) -> Result<String, InputError> {
+    fs::read_to_string(path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => InputError::Missing(path.to_path_buf()),
+        _ => InputError::Io(path.to_path_buf(), e),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn loads_day_relative_to_inputs_dir() {
+        assert!(load_day(1).unwrap().starts_with("+12"));
+    }
+
+    #[test]
+    fn missing_file_names_the_path() {
+        let error = InputSource::from_arg("no/such/day42.txt")
+            .load()
+            .unwrap_err();
+        match &error {
+            InputError::Missing(path) => assert_eq!(path, Path::new("no/such/day42.txt")),
+            other => panic!("expected Missing, got {:?}", other),
+        }
+        assert!(error.to_string().contains("no/such/day42.txt"));
+    }
+
+    #[test]
+    fn dash_means_stdin() {
+        assert_eq!(InputSource::from_arg("-"), InputSource::Stdin);
+        assert_eq!(InputSource::Stdin.to_string(), "<stdin>");
+    }
+}
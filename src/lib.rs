@@ -0,0 +1,31 @@
+//! Solvers for Advent of Code 2018, one module per day.
+//!
+//! Every day implements [`Solution`](solution::Solution) and is listed in
+//! [`solution::DAYS`], so tooling can treat them uniformly.
+
+#[macro_use]
+extern crate lazy_static;
+
+pub mod day1;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+
+pub mod day10;
+
+pub mod answers;
+pub mod bench;
+pub mod bk_tree;
+pub mod error;
+pub mod generate;
+pub mod input;
+pub mod report;
+pub mod solution;
+pub mod verify;
+
+pub use solution::{Parsed, Solution, Solver};
@@ -1,19 +1,580 @@
-#[cfg(test)]
-#[macro_use]
-extern crate lazy_static;
+use advent2018::answers;
+use advent2018::answers::Answers;
+use advent2018::bench;
+use advent2018::day1;
+use advent2018::day3;
+use advent2018::day3::render::Colouring;
+use advent2018::day3::{Rect, Renderer};
+use advent2018::input::{InputSource, INPUTS_DIR_VAR};
+use advent2018::report;
+use advent2018::report::PartReport;
+use advent2018::solution;
+use advent2018::verify;
+use advent2018::Solver;
+use std::env;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "usage: advent2018 run <day> [--part 1|2] [--input PATH|-] [--format text|json]
+       advent2018 run --all [--format text|json]
+       advent2018 verify [--answers PATH]
+       advent2018 bench [<day>|--all] [--runs N]
+       advent2018 repeats [--cycles K] [--input PATH|-] [--format text|json]
+       advent2018 render [--input PATH|-] [--image ascii|ppm|png] [--colour depth|claim]
+                         [--crop LEFT,TOP:WIDTHxHEIGHT] [--highlight] [--output PATH]";
+
+/// Repetitions of each stage when `--runs` isn't given
+const DEFAULT_RUNS: usize = 5;
 
-mod day1;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
+/// Day 1 cycles searched for revisited frequencies when `--cycles` isn't given
+const DEFAULT_CYCLES: usize = 200;
 
-mod day10;
+#[derive(Debug, PartialEq)]
+enum Command {
+    Run(RunArgs),
+    /// `None` means answers.toml alongside the inputs
+    Verify(Option<PathBuf>),
+    Bench(BenchArgs),
+    /// day 1's revisited frequencies
+    Repeats(RepeatsArgs),
+    /// a picture of day 3's claims
+    Render(RenderArgs),
+}
+
+#[derive(Debug, PartialEq)]
+struct RunArgs {
+    /// `None` means every registered day
+    day: Option<u8>,
+    part: Option<u8>,
+    input: Option<InputSource>,
+    format: Format,
+}
+
+#[derive(Debug, PartialEq)]
+enum Format {
+    Text,
+    /// one JSON object per line, per day and part
+    Json,
+}
+
+#[derive(Debug, PartialEq)]
+struct BenchArgs {
+    /// `None` means every registered day
+    day: Option<u8>,
+    runs: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct RenderArgs {
+    input: Option<InputSource>,
+    image: Image,
+    colouring: Colouring,
+    crop: Option<Rect>,
+    highlight: bool,
+    /// `None` means stdout
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Image {
+    Ascii,
+    Ppm,
+    Png,
+}
+
+#[derive(Debug, PartialEq)]
+struct RepeatsArgs {
+    cycles: usize,
+    input: Option<InputSource>,
+    format: Format,
+}
 
 fn main() {
-    println!("Hello, world!");
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let command = match parse_args(&args) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            eprintln!(
+                "\nInputs default to dayN.txt in the crate root, or in ${} if set",
+                INPUTS_DIR_VAR
+            );
+            process::exit(2);
+        }
+    };
+
+    let result = match &command {
+        Command::Run(run_args) => run(run_args),
+        Command::Verify(answers_path) => verify(answers_path),
+        Command::Bench(bench_args) => bench(bench_args),
+        Command::Repeats(repeats_args) => repeats(repeats_args),
+        Command::Render(render_args) => render(render_args),
+    };
+    if let Err(message) = result {
+        eprintln!("{}", message);
+        process::exit(1);
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    match args.first().map(String::as_str) {
+        Some("run") => parse_run_args(&args[1..]).map(Command::Run),
+        Some("verify") => parse_verify_args(&args[1..]).map(Command::Verify),
+        Some("bench") => parse_bench_args(&args[1..]).map(Command::Bench),
+        Some("repeats") => parse_repeats_args(&args[1..]).map(Command::Repeats),
+        Some("render") => parse_render_args(&args[1..]).map(Command::Render),
+        Some(other) => Err(format!("unknown command '{}'", other)),
+        None => Err("no command given".to_string()),
+    }
+}
+
+fn parse_verify_args(args: &[String]) -> Result<Option<PathBuf>, String> {
+    match args {
+        [] => Ok(None),
+        [flag, path] if flag == "--answers" => Ok(Some(PathBuf::from(path))),
+        _ => Err(format!("unexpected arguments '{}'", args.join(" "))),
+    }
+}
+
+fn parse_bench_args(args: &[String]) -> Result<BenchArgs, String> {
+    let mut args = args.iter();
+
+    let mut all = false;
+    let mut day = None;
+    let mut runs = DEFAULT_RUNS;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--all" => all = true,
+            "--runs" => {
+                let value = args.next().ok_or("--runs needs a value")?;
+                runs = match value.parse() {
+                    Ok(runs) if runs > 0 => runs,
+                    _ => return Err(format!("--runs must be a positive number, not '{}'", value)),
+                };
+            }
+            value if day.is_none() && !value.starts_with("--") => day = Some(parse_day(value)?),
+            other => return Err(format!("unexpected argument '{}'", other)),
+        }
+    }
+
+    if all && day.is_some() {
+        return Err("--all can't be combined with a day".to_string());
+    }
+    Ok(BenchArgs { day, runs })
+}
+
+fn parse_repeats_args(args: &[String]) -> Result<RepeatsArgs, String> {
+    let mut args = args.iter();
+
+    let mut cycles = DEFAULT_CYCLES;
+    let mut input = None;
+    let mut format = Format::Text;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cycles" => {
+                let value = args.next().ok_or("--cycles needs a value")?;
+                cycles = value
+                    .parse()
+                    .map_err(|_| format!("--cycles must be a number, not '{}'", value))?;
+            }
+            "--input" => {
+                let value = args.next().ok_or("--input needs a value")?;
+                input = Some(InputSource::from_arg(value));
+            }
+            "--format" => format = parse_format(args.next())?,
+            other => return Err(format!("unexpected argument '{}'", other)),
+        }
+    }
+
+    Ok(RepeatsArgs {
+        cycles,
+        input,
+        format,
+    })
+}
+
+fn parse_render_args(args: &[String]) -> Result<RenderArgs, String> {
+    let mut args = args.iter();
+
+    let mut render_args = RenderArgs {
+        input: None,
+        image: Image::Ascii,
+        colouring: Colouring::Depth,
+        crop: None,
+        highlight: false,
+        output: None,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => {
+                let value = args.next().ok_or("--input needs a value")?;
+                render_args.input = Some(InputSource::from_arg(value));
+            }
+            "--image" => {
+                let value = args.next().ok_or("--image needs a value")?;
+                render_args.image = match value.as_str() {
+                    "ascii" => Image::Ascii,
+                    "ppm" => Image::Ppm,
+                    "png" => Image::Png,
+                    _ => {
+                        return Err(format!(
+                            "--image must be ascii, ppm or png, not '{}'",
+                            value
+                        ))
+                    }
+                };
+            }
+            "--colour" => {
+                let value = args.next().ok_or("--colour needs a value")?;
+                render_args.colouring = match value.as_str() {
+                    "depth" => Colouring::Depth,
+                    "claim" => Colouring::Claim,
+                    _ => return Err(format!("--colour must be depth or claim, not '{}'", value)),
+                };
+            }
+            "--crop" => {
+                let value = args.next().ok_or("--crop needs a value")?;
+                render_args.crop = Some(parse_crop(value)?);
+            }
+            "--highlight" => render_args.highlight = true,
+            "--output" => {
+                let value = args.next().ok_or("--output needs a value")?;
+                render_args.output = Some(PathBuf::from(value));
+            }
+            other => return Err(format!("unexpected argument '{}'", other)),
+        }
+    }
+
+    Ok(render_args)
+}
+
+/// A rectangle written like a claim, e.g. `100,200:50x40`
+fn parse_crop(value: &str) -> Result<Rect, String> {
+    let claim: day3::Claim = format!("#0 @ {}", value.replace(':', ": "))
+        .parse()
+        .map_err(|_| {
+            format!(
+                "--crop must look like LEFT,TOP:WIDTHxHEIGHT, not '{}'",
+                value
+            )
+        })?;
+    if claim.area() == 0 {
+        return Err(format!("--crop needs a width and height, not '{}'", value));
+    }
+    Ok(Rect::new(
+        claim.offset_left,
+        claim.offset_top,
+        claim.width,
+        claim.height,
+    ))
+}
+
+fn parse_format(value: Option<&String>) -> Result<Format, String> {
+    match value.map(String::as_str) {
+        Some("text") => Ok(Format::Text),
+        Some("json") => Ok(Format::Json),
+        Some(other) => Err(format!("--format must be text or json, not '{}'", other)),
+        None => Err("--format needs a value".to_string()),
+    }
+}
+
+fn parse_day(value: &str) -> Result<u8, String> {
+    value
+        .trim_start_matches("day")
+        .parse()
+        .map_err(|_| format!("'{}' is not a day number", value))
+}
+
+fn parse_run_args(args: &[String]) -> Result<RunArgs, String> {
+    let mut args = args.iter();
+
+    let mut all = false;
+    let mut day = None;
+    let mut part = None;
+    let mut input = None;
+    let mut format = Format::Text;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--all" => all = true,
+            "--part" => {
+                let value = args.next().ok_or("--part needs a value")?;
+                part = match value.as_str() {
+                    "1" => Some(1),
+                    "2" => Some(2),
+                    _ => return Err(format!("--part must be 1 or 2, not '{}'", value)),
+                };
+            }
+            "--input" => {
+                let value = args.next().ok_or("--input needs a value")?;
+                input = Some(InputSource::from_arg(value));
+            }
+            "--format" => format = parse_format(args.next())?,
+            value if day.is_none() && !value.starts_with("--") => day = Some(parse_day(value)?),
+            other => return Err(format!("unexpected argument '{}'", other)),
+        }
+    }
+
+    match (all, day) {
+        (true, Some(_)) => Err("--all can't be combined with a day".to_string()),
+        (true, None) if input.is_some() => Err("--input needs a single day".to_string()),
+        (true, None) => Ok(RunArgs {
+            day: None,
+            part,
+            input,
+            format,
+        }),
+        (false, None) => Err("no day given".to_string()),
+        (false, Some(day)) => Ok(RunArgs {
+            day: Some(day),
+            part,
+            input,
+            format,
+        }),
+    }
+}
+
+fn solvers(day: Option<u8>) -> Result<Vec<&'static dyn Solver>, String> {
+    match day {
+        Some(day) => Ok(vec![
+            solution::find(day).ok_or(format!("no solver registered for day {}", day))?
+        ]),
+        None => Ok(solution::DAYS.to_vec()),
+    }
+}
+
+fn run(args: &RunArgs) -> Result<(), String> {
+    let mut failures = 0;
+    for solver in solvers(args.day)? {
+        let source = match &args.input {
+            Some(source) => source.clone(),
+            None => InputSource::Day(solver.day()),
+        };
+
+        for part_report in report::solve(solver, &source, args.part) {
+            if !part_report.succeeded() {
+                failures += 1;
+            }
+            match args.format {
+                Format::Text => print_report(&part_report),
+                Format::Json => println!("{}", part_report.to_json()),
+            }
+        }
+    }
+
+    match failures {
+        0 => Ok(()),
+        1 => Err("1 part failed".to_string()),
+        n => Err(format!("{} parts failed", n)),
+    }
+}
+
+fn verify(answers_path: &Option<PathBuf>) -> Result<(), String> {
+    let path = answers_path.clone().unwrap_or_else(answers::default_path);
+    let manifest = fs::read_to_string(&path)
+        .map_err(|e| format!("couldn't read '{}': {}", path.display(), e))?;
+    let answers = Answers::parse(&manifest).map_err(|e| e.in_file(path.display()).to_string())?;
+
+    let outcomes = verify::verify_all(&answers, |outcome| println!("{}", outcome));
+
+    let passed = outcomes.iter().filter(|outcome| outcome.passed()).count();
+    if passed == outcomes.len() {
+        Ok(())
+    } else {
+        Err(format!("{} of {} answers verified", passed, outcomes.len()))
+    }
+}
+
+fn bench(args: &BenchArgs) -> Result<(), String> {
+    println!("{}", bench::HEADER);
+    for solver in solvers(args.day)? {
+        let source = InputSource::Day(solver.day());
+        let input = source.load().map_err(|e| e.to_string())?;
+        let timings =
+            bench::bench(solver, &input, args.runs).map_err(|e| e.in_file(&source).to_string())?;
+        print!("{}", timings);
+    }
+    Ok(())
+}
+
+fn repeats(args: &RepeatsArgs) -> Result<(), String> {
+    let source = args.input.clone().unwrap_or(InputSource::Day(1));
+    let input = source.load().map_err(|e| e.to_string())?;
+    let changes = day1::parse(&input).map_err(|e| e.in_file(&source).to_string())?;
+
+    let report =
+        day1::repeat_report(&changes, args.cycles).map_err(|e| format!("{}:{}", source, e))?;
+    match args.format {
+        Format::Text => print!("{}", report),
+        Format::Json => println!("{}", report.to_json()),
+    }
+    Ok(())
+}
+
+fn render(args: &RenderArgs) -> Result<(), String> {
+    let source = args.input.clone().unwrap_or(InputSource::Day(3));
+    let input = source.load().map_err(|e| e.to_string())?;
+    let claims = day3::parse(&input).map_err(|e| e.in_file(&source).to_string())?;
+
+    let mut renderer = Renderer::new(&claims)
+        .colouring(args.colouring)
+        .highlight_uncontended(args.highlight);
+    if let Some(crop) = args.crop {
+        renderer = renderer.crop(crop);
+    }
+    let area = renderer.area();
+    if args.image != Image::Ascii && area.area() == 0 {
+        return Err(format!(
+            "nothing to render: the area is {}x{}",
+            area.width, area.height
+        ));
+    }
+    let image = match args.image {
+        Image::Ascii => renderer.ascii().into_bytes(),
+        Image::Ppm => renderer.ppm(),
+        Image::Png => renderer.png(),
+    };
+
+    match &args.output {
+        Some(path) => fs::write(path, image)
+            .map_err(|e| format!("couldn't write '{}': {}", path.display(), e)),
+        None => io::stdout()
+            .write_all(&image)
+            .map_err(|e| format!("couldn't write the image: {}", e)),
+    }
+}
+
+fn print_report(report: &PartReport) {
+    let (day, part) = (report.day, report.part);
+    match &report.result {
+        Ok(answer) if answer.contains('\n') => {
+            println!("Day {}, part {}:\n{}", day, part, answer.trim_end())
+        }
+        Ok(answer) => println!("Day {}, part {}: {}", day, part, answer),
+        Err(message) => eprintln!("Day {}, part {}: {}", day, part, message),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parses_single_day() {
+        assert_eq!(
+            parse_args(&args("run 3 --part 2 --input foo.txt")),
+            Ok(Command::Run(RunArgs {
+                day: Some(3),
+                part: Some(2),
+                input: Some(InputSource::Path("foo.txt".into())),
+                format: Format::Text,
+            }))
+        );
+    }
+
+    #[test]
+    fn dash_reads_stdin() {
+        assert_eq!(
+            parse_run_args(&args("1 --input -")).map(|run_args| run_args.input),
+            Ok(Some(InputSource::Stdin))
+        );
+    }
+
+    #[test]
+    fn parses_all_days() {
+        assert_eq!(
+            parse_args(&args("run --all --format json")),
+            Ok(Command::Run(RunArgs {
+                day: None,
+                part: None,
+                input: None,
+                format: Format::Json,
+            }))
+        );
+    }
+
+    #[test]
+    fn parses_verify() {
+        assert_eq!(parse_args(&args("verify")), Ok(Command::Verify(None)));
+        assert_eq!(
+            parse_args(&args("verify --answers mine.toml")),
+            Ok(Command::Verify(Some(PathBuf::from("mine.toml"))))
+        );
+    }
+
+    #[test]
+    fn parses_bench() {
+        assert_eq!(
+            parse_args(&args("bench")),
+            Ok(Command::Bench(BenchArgs {
+                day: None,
+                runs: DEFAULT_RUNS,
+            }))
+        );
+        assert_eq!(
+            parse_args(&args("bench day9 --runs 20")),
+            Ok(Command::Bench(BenchArgs {
+                day: Some(9),
+                runs: 20,
+            }))
+        );
+    }
+
+    #[test]
+    fn parses_repeats() {
+        assert_eq!(
+            parse_args(&args("repeats --cycles 3 --format json")),
+            Ok(Command::Repeats(RepeatsArgs {
+                cycles: 3,
+                input: None,
+                format: Format::Json,
+            }))
+        );
+    }
+
+    #[test]
+    fn parses_render() {
+        assert_eq!(
+            parse_args(&args(
+                "render --image png --colour claim --crop 10,20:30x40 --highlight --output a.png"
+            )),
+            Ok(Command::Render(RenderArgs {
+                input: None,
+                image: Image::Png,
+                colouring: Colouring::Claim,
+                crop: Some(Rect::new(10, 20, 30, 40)),
+                highlight: true,
+                output: Some(PathBuf::from("a.png")),
+            }))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(parse_args(&args("")).is_err());
+        assert!(parse_args(&args("run")).is_err());
+        assert!(parse_args(&args("run 3 --part 3")).is_err());
+        assert!(parse_args(&args("run 3 --all")).is_err());
+        assert!(parse_args(&args("run --all --input foo.txt")).is_err());
+        assert!(parse_args(&args("run 3 --format xml")).is_err());
+        assert!(parse_args(&args("verify --answers")).is_err());
+        assert!(parse_args(&args("bench 3 --all")).is_err());
+        assert!(parse_args(&args("bench --runs 0")).is_err());
+        assert!(parse_args(&args("repeats --cycles")).is_err());
+        assert!(parse_args(&args("repeats 1")).is_err());
+        assert!(parse_args(&args("render --image gif")).is_err());
+        assert!(parse_args(&args("render --crop 10x20")).is_err());
+        assert!(parse_args(&args("render --crop 1,2:0x20")).is_err());
+    }
 }
@@ -0,0 +1,181 @@
+use std::any::Any;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+
+use crate::bench::time;
+use crate::input::InputSource;
+use crate::solution::Solver;
+
+/// The outcome of solving one part of one day
+#[derive(Debug, PartialEq)]
+pub struct PartReport {
+    pub day: u8,
+    pub part: u8,
+    pub input: String,
+    /// the answer, or why there isn't one
+    pub result: Result<String, String>,
+    pub parse_time: Duration,
+    pub elapsed: Duration,
+}
+
+impl PartReport {
+    pub fn succeeded(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// A single-line JSON object, e.g. for JSON Lines output
+    pub fn to_json(&self) -> String {
+        let (answer, error) = match &self.result {
+            Ok(answer) => (json_string(answer), "null".to_string()),
+            Err(message) => ("null".to_string(), json_string(message)),
+        };
+        format!(
+            r#"{{"day":{},"part":{},"input":{},"answer":{},"error":{},"parse_ns":{},"elapsed_ns":{}}}"#,
+            self.day,
+            self.part,
+            json_string(&self.input),
+            answer,
+            error,
+            self.parse_time.as_nanos(),
+            self.elapsed.as_nanos()
+        )
+    }
+}
+
+/// Loads, parses and solves the requested parts (both if `None`), turning failures and panics
+/// into error reports
+pub fn solve(solver: &dyn Solver, source: &InputSource, part: Option<u8>) -> Vec<PartReport> {
+    let parts: Vec<u8> = match part {
+        Some(part) => vec![part],
+        None => vec![1, 2],
+    };
+    let report = |part, result, parse_time, elapsed| PartReport {
+        day: solver.day(),
+        part,
+        input: source.to_string(),
+        result,
+        parse_time,
+        elapsed,
+    };
+
+    let input = match source.load() {
+        Ok(input) => input,
+        Err(e) => {
+            return parts
+                .iter()
+                .map(|&part| {
+                    report(
+                        part,
+                        Err(e.to_string()),
+                        Duration::default(),
+                        Duration::default(),
+                    )
+                })
+                .collect()
+        }
+    };
+
+    let (parsed, parse_time) = time(|| catch(|| solver.parse(&input)));
+    let parsed = match parsed.and_then(|parsed| parsed.map_err(|e| e.in_file(source).to_string())) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            return parts
+                .iter()
+                .map(|&part| report(part, Err(message.clone()), parse_time, Duration::default()))
+                .collect()
+        }
+    };
+
+    parts
+        .iter()
+        .map(|&part| {
+            let (result, elapsed) = time(|| {
+                catch(|| match part {
+                    1 => parsed.part1(),
+                    _ => parsed.part2(),
+                })
+            });
+            report(part, result, parse_time, elapsed)
+        })
+        .collect()
+}
+
+fn catch<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| panic_message(&*payload))
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause");
+    format!("solver panicked: {}", message)
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solution::find;
+
+    #[test]
+    fn json_escapes_answers() {
+        let report = PartReport {
+            day: 10,
+            part: 1,
+            input: "C:\\day10.txt".to_string(),
+            result: Ok("#..#\n\"##\"".to_string()),
+            parse_time: Duration::from_nanos(5),
+            elapsed: Duration::from_micros(2),
+        };
+        assert_eq!(
+            report.to_json(),
+            r###"{"day":10,"part":1,"input":"C:\\day10.txt","answer":"#..#\n\"##\"","error":null,"parse_ns":5,"elapsed_ns":2000}"###
+        );
+    }
+
+    #[test]
+    fn solves_requested_parts() {
+        let reports = solve(find(8).unwrap(), &InputSource::Day(8), Some(2));
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].part, 2);
+        assert_eq!(reports[0].result, Ok("33891".to_string()));
+        assert!(reports[0].input.ends_with("day8.txt"));
+    }
+
+    #[test]
+    fn failures_are_reported_per_part() {
+        let source = InputSource::from_arg("no/such/day8.txt");
+        let reports = solve(find(8).unwrap(), &source, None);
+        assert_eq!(reports.len(), 2);
+        assert!(reports.iter().all(|report| !report.succeeded()));
+        assert!(reports[0]
+            .to_json()
+            .contains(r#""answer":null,"error":"input file"#));
+    }
+
+    #[test]
+    fn panics_become_errors() {
+        let result = catch(|| -> u8 { panic!("boom") });
+        assert_eq!(result, Err("solver panicked: boom".to_string()));
+    }
+}
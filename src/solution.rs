@@ -0,0 +1,108 @@
+use std::fmt::Display;
+
+use crate::error::ParseError;
+
+use crate::day1::Day1;
+use crate::day10::Day10;
+use crate::day2::Day2;
+use crate::day3::Day3;
+use crate::day4::Day4;
+use crate::day5::Day5;
+use crate::day6::Day6;
+use crate::day7::Day7;
+use crate::day8::Day8;
+use crate::day9::Day9;
+
+/// A day's puzzle: the input is parsed once, then both parts are answered from the parsed form
+pub trait Solution {
+    const DAY: u8;
+
+    type Input;
+    type Part1: Display;
+    type Part2: Display;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError>;
+    fn part1(input: &Self::Input) -> Self::Part1;
+    fn part2(input: &Self::Input) -> Self::Part2;
+}
+
+/// Object-safe view of a `Solution`, so days with different input types can share one registry
+pub trait Solver: Sync {
+    fn day(&self) -> u8;
+    fn parse(&self, input: &str) -> Result<Box<dyn Parsed>, ParseError>;
+}
+
+/// A parsed input, ready to answer either part
+pub trait Parsed {
+    fn part1(&self) -> String;
+    fn part2(&self) -> String;
+}
+
+struct Prepared<S: Solution>(S::Input);
+
+impl<S: Solution> Parsed for Prepared<S> {
+    fn part1(&self) -> String {
+        S::part1(&self.0).to_string()
+    }
+
+    fn part2(&self) -> String {
+        S::part2(&self.0).to_string()
+    }
+}
+
+impl<S> Solver for S
+where
+    S: Solution + Sync + 'static,
+    S::Input: 'static,
+{
+    fn day(&self) -> u8 {
+        S::DAY
+    }
+
+    fn parse(&self, input: &str) -> Result<Box<dyn Parsed>, ParseError> {
+        let parsed: Box<dyn Parsed> = Box::new(Prepared::<S>(S::parse(input)?));
+        Ok(parsed)
+    }
+}
+
+/// Every day that has a solver, in order
+pub static DAYS: [&dyn Solver; 10] = [
+    &Day1, &Day2, &Day3, &Day4, &Day5, &Day6, &Day7, &Day8, &Day9, &Day10,
+];
+
+pub fn find(day: u8) -> Option<&'static dyn Solver> {
+    DAYS.iter().find(|solver| solver.day() == day).copied()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn registry_is_in_day_order() {
+        let days: Vec<u8> = DAYS.iter().map(|solver| solver.day()).collect();
+        assert_eq!(days, (1..=10).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn find_by_day_number() {
+        assert_eq!(find(8).map(|solver| solver.day()), Some(8));
+        assert!(find(11).is_none());
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        let error = find(8).unwrap().parse("2 3 0").err().unwrap();
+        assert_eq!(error.column, 6);
+    }
+
+    #[test]
+    fn parsed_input_answers_both_parts() {
+        let parsed = find(8)
+            .unwrap()
+            .parse("2 3 0 3 10 11 12 1 1 0 1 99 2 1 1 2")
+            .unwrap();
+        assert_eq!(parsed.part1(), "138");
+        assert_eq!(parsed.part2(), "66");
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::answers::Answers;
+use crate::input::InputSource;
+use crate::report;
+use crate::solution::{Solver, DAYS};
+
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    Pass,
+    Fail {
+        expected: String,
+    },
+    /// the manifest has no answer for this part
+    Missing,
+    /// the input couldn't be loaded or parsed, or the solver panicked
+    Error(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Outcome {
+    pub day: u8,
+    pub part: u8,
+    pub answer: Option<String>,
+    pub status: Status,
+}
+
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        self.status == Status::Pass
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let answer = self.answer.as_deref().unwrap_or("");
+        write!(f, "Day {}, part {}: ", self.day, self.part)?;
+        match &self.status {
+            Status::Pass => write!(f, "pass"),
+            Status::Fail { expected } => {
+                write!(f, "FAIL\n  expected: {}\n  actual:   {}", expected, answer)
+            }
+            Status::Missing => write!(f, "missing (answer was {})", answer),
+            Status::Error(message) => write!(f, "ERROR {}", message),
+        }
+    }
+}
+
+/// Runs every registered day against its default input, passing each outcome to `progress` as
+/// soon as it's known
+pub fn verify_all(answers: &Answers, mut progress: impl FnMut(&Outcome)) -> Vec<Outcome> {
+    let mut outcomes = Vec::new();
+    for solver in DAYS.iter() {
+        for outcome in verify(*solver, &InputSource::Day(solver.day()), answers) {
+            progress(&outcome);
+            outcomes.push(outcome);
+        }
+    }
+    outcomes
+}
+
+/// Runs both parts of one day, comparing against the manifest
+pub fn verify(solver: &dyn Solver, source: &InputSource, answers: &Answers) -> Vec<Outcome> {
+    report::solve(solver, source, None)
+        .into_iter()
+        .map(|report| {
+            let (day, part) = (report.day, report.part);
+            let (answer, status) = match report.result {
+                Ok(answer) => {
+                    let status = match answers.get(day, part) {
+                        Some(expected) if same_answer(expected, &answer) => Status::Pass,
+                        Some(expected) => Status::Fail {
+                            expected: expected.to_string(),
+                        },
+                        None => Status::Missing,
+                    };
+                    (Some(answer), status)
+                }
+                Err(message) => (None, Status::Error(message)),
+            };
+            Outcome {
+                day,
+                part,
+                answer,
+                status,
+            }
+        })
+        .collect()
+}
+
+/// Multi-line answers (e.g. day 10's rendered message) ignore trailing whitespace on each line
+fn same_answer(expected: &str, actual: &str) -> bool {
+    let normalise = |s: &str| -> Vec<String> {
+        let lines: Vec<String> = s.lines().map(|line| line.trim_end().to_string()).collect();
+        let end = lines
+            .iter()
+            .rposition(|line| !line.is_empty())
+            .map_or(0, |i| i + 1);
+        lines[..end].to_vec()
+    };
+    normalise(expected) == normalise(actual)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::answers;
+    use crate::error::ParseError;
+    use crate::solution::find;
+    use crate::solution::Solution;
+    use std::fs;
+
+    #[test]
+    fn every_day_matches_answers_manifest() {
+        let manifest = fs::read_to_string(answers::default_path()).unwrap();
+        let mut printed = 0;
+        let outcomes = verify_all(&Answers::parse(&manifest).unwrap(), |_| printed += 1);
+        assert_eq!(printed, outcomes.len());
+
+        let failures: Vec<String> = outcomes
+            .iter()
+            .filter(|outcome| !outcome.passed())
+            .map(|outcome| outcome.to_string())
+            .collect();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+        assert_eq!(outcomes.len(), 20);
+    }
+
+    #[test]
+    fn reports_fail_and_missing() {
+        let mut answers = Answers::default();
+        answers.insert(8, 1, "1");
+
+        let outcomes = verify(find(8).unwrap(), &InputSource::Day(8), &answers);
+        assert_eq!(
+            outcomes[0].status,
+            Status::Fail {
+                expected: "1".to_string()
+            }
+        );
+        assert_eq!(outcomes[1].status, Status::Missing);
+        assert_eq!(outcomes[1].answer, Some("33891".to_string()));
+    }
+
+    #[test]
+    fn reports_unreadable_input() {
+        let source = InputSource::from_arg("no/such/day8.txt");
+        let outcomes = verify(find(8).unwrap(), &source, &Answers::default());
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes
+            .iter()
+            .all(|outcome| matches!(outcome.status, Status::Error(_))));
+    }
+
+    struct Panicky;
+
+    impl Solution for Panicky {
+        const DAY: u8 = 99;
+
+        type Input = ();
+        type Part1 = u8;
+        type Part2 = u8;
+
+        fn parse(_input: &str) -> Result<(), ParseError> {
+            Ok(())
+        }
+
+        fn part1(_input: &()) -> u8 {
+            panic!("no answer")
+        }
+
+        fn part2(_input: &()) -> u8 {
+            2
+        }
+    }
+
+    #[test]
+    fn panics_become_errors() {
+        let mut answers = Answers::default();
+        answers.insert(99, 2, "2");
+
+        let outcomes = verify(&Panicky, &InputSource::Day(8), &answers);
+        assert_eq!(
+            outcomes[0].status,
+            Status::Error("solver panicked: no answer".to_string())
+        );
+        assert_eq!(outcomes[1].status, Status::Pass);
+    }
+
+    #[test]
+    fn multiline_answers_ignore_trailing_whitespace() {
+        assert!(same_answer("#..#\n####", "#..#  \n####\n"));
+        assert!(!same_answer("#..#\n####", "#..#\n###."));
+    }
+}